@@ -1,25 +1,14 @@
 use itertools::Itertools;
 use yaah::{aoc, aoc_generator};
 
+use crate::expr::Expr;
+
+/// The `old` worry level of an item being inspected.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub enum MulOp {
-    Imm(usize),
-    Old,
-}
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub enum InspectOp {
-    Add(usize),
-    Mul(MulOp),
-}
-impl InspectOp {
-    fn inspect(&self, v: usize) -> usize {
-        match *self {
-            InspectOp::Add(imm) => v + imm,
-            InspectOp::Mul(MulOp::Imm(imm)) => v * imm,
-            InspectOp::Mul(MulOp::Old) => v * v,
-        }
-    }
-}
+pub struct Old;
+
+/// Expression giving the new worry level of an inspected item.
+pub type InspectOp = Expr<Old, usize>;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Monkey {
@@ -29,7 +18,7 @@ pub struct Monkey {
 }
 
 #[aoc_generator(day11)]
-fn day11(input: &'static str) -> Vec<Monkey> {
+pub fn day11(input: &'static str) -> Vec<Monkey> {
     input
         .split("\n\n")
         .filter_map(|monkey| {
@@ -42,16 +31,11 @@ fn day11(input: &'static str) -> Vec<Monkey> {
                 .filter_map(|v| v.parse().ok())
                 .collect_vec();
 
-            let (operand, operator) = lines.next()?.split(' ').rev().take(2).collect_tuple()?;
-            let inspect = match (operator, operand) {
-                ("+", imm) => InspectOp::Add(imm.parse().ok()?),
-                ("*", "old") => InspectOp::Mul(MulOp::Old),
-                ("*", imm) => InspectOp::Mul(MulOp::Imm(imm.parse().ok()?)),
-                _ => unreachable!(),
-            };
+            let (_, expr) = lines.next()?.split_once(" = ")?;
+            let inspect = InspectOp::parse(expr, |v| (v == "old").then_some(Old)).ok()?;
 
             let check = lines
-                .filter_map(|l| l.split(' ').last()?.parse().ok())
+                .filter_map(|l| l.split(' ').next_back()?.parse().ok())
                 .collect_tuple()?;
             Some(Monkey {
                 items,
@@ -62,50 +46,111 @@ fn day11(input: &'static str) -> Vec<Monkey> {
         .collect()
 }
 
-#[aoc(day11, part1)]
-fn day11_part1(monkeys: &[Monkey]) -> usize {
-    let mut monkeys = monkeys.to_vec();
-    let mut monkey_businesses = vec![0; monkeys.len()];
+/// Worry-level relief applied after a monkey inspects an item.
+pub trait Relief {
+    fn relieve(&self, worry: usize) -> usize;
+}
 
-    for _round in 0..20 {
-        for monkey_id in 0..monkeys.len() {
-            let items = std::mem::take(&mut monkeys[monkey_id].items);
-            monkey_businesses[monkey_id] += items.len();
+/// Relief of part 1: worry levels are divided after each inspection.
+pub struct DivideBy(pub usize);
+impl Relief for DivideBy {
+    fn relieve(&self, worry: usize) -> usize {
+        worry / self.0
+    }
+}
 
-            items.into_iter().for_each(|v| {
-                let monkey = &monkeys[monkey_id];
-                let mut new = monkey.inspect.inspect(v);
-                new /= 3;
-                let target = if (new % monkey.check.0) == 0 {
-                    monkey.check.1
-                } else {
-                    monkey.check.2
-                };
-                monkeys[target].items.push(new);
-            });
+/// Relief of part 2: worry levels are kept bounded by the product of all the monkeys' divisors,
+/// which preserves the outcome of every divisibility test.
+pub struct ModProduct(usize);
+impl ModProduct {
+    pub fn new(monkeys: &[Monkey]) -> Self {
+        Self(monkeys.iter().map(|m| m.check.0).product())
+    }
+}
+impl Relief for ModProduct {
+    fn relieve(&self, worry: usize) -> usize {
+        worry % self.0
+    }
+}
+
+/// State of the monkeys at the end of a round.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Round {
+    /// Number of items inspected by each monkey since the start of the simulation.
+    pub inspections: Vec<usize>,
+    /// Worry levels of the items held by each monkey.
+    pub items: Vec<Vec<usize>>,
+}
+impl Round {
+    pub fn monkey_business(&self) -> usize {
+        monkey_business(&self.inspections)
+    }
+}
+
+/// Product of the two highest inspection counts.
+fn monkey_business(inspections: &[usize]) -> usize {
+    inspections.iter().sorted_unstable().rev().take(2).product()
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct History(pub Vec<Round>);
+impl History {
+    pub fn monkey_business(&self) -> usize {
+        self.0.last().map(Round::monkey_business).unwrap_or(0)
+    }
+
+    pub fn monkey_business_over_time(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.iter().map(Round::monkey_business)
+    }
+
+    pub fn csv(&self) -> Csv<'_> {
+        Csv(self)
+    }
+}
+
+/// Dumps an history as CSV, one row per monkey and per round.
+pub struct Csv<'a>(&'a History);
+impl std::fmt::Display for Csv<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "round,monkey,inspections,items")?;
+        for (round, state) in self.0 .0.iter().enumerate() {
+            for (monkey, (inspections, items)) in
+                state.inspections.iter().zip(state.items.iter()).enumerate()
+            {
+                writeln!(
+                    f,
+                    "{},{},{},{}",
+                    round + 1,
+                    monkey,
+                    inspections,
+                    items.iter().join(" ")
+                )?;
+            }
         }
+        Ok(())
     }
-    monkey_businesses.sort_unstable();
-    monkey_businesses.into_iter().rev().take(2).product()
 }
 
-#[aoc(day11, part2)]
-fn day11_part2(monkeys: &[Monkey]) -> usize {
+/// Runs `rounds` rounds, calling `on_round` with the inspection counts and the monkeys at the end
+/// of each, and returns the final inspection counts.
+pub fn simulate_with(
+    monkeys: &[Monkey],
+    relief: &impl Relief,
+    rounds: usize,
+    mut on_round: impl FnMut(&[usize], &[Monkey]),
+) -> Vec<usize> {
     let mut monkeys = monkeys.to_vec();
-    let mut monkey_businesses = vec![0; monkeys.len()];
-
-    let master_mod: usize = monkeys.iter().map(|m| m.check.0).product();
+    let mut inspections = vec![0; monkeys.len()];
 
-    for _round in 0..10000 {
+    for _round in 0..rounds {
         for monkey_id in 0..monkeys.len() {
             let items = std::mem::take(&mut monkeys[monkey_id].items);
-            monkey_businesses[monkey_id] += items.len();
+            inspections[monkey_id] += items.len();
 
             items.into_iter().for_each(|v| {
                 let monkey = &monkeys[monkey_id];
-                let mut new = monkey.inspect.inspect(v);
-                new %= master_mod;
-                let target = if (new % monkey.check.0) == 0 {
+                let new = relief.relieve(monkey.inspect.eval(|Old| v));
+                let target = if new.is_multiple_of(monkey.check.0) {
                     monkey.check.1
                 } else {
                     monkey.check.2
@@ -113,9 +158,32 @@ fn day11_part2(monkeys: &[Monkey]) -> usize {
                 monkeys[target].items.push(new);
             });
         }
+        on_round(&inspections, &monkeys);
     }
-    monkey_businesses.sort_unstable();
-    monkey_businesses.into_iter().rev().take(2).product()
+    inspections
+}
+
+/// Runs `rounds` rounds, recording the state at the end of each.
+pub fn simulate(monkeys: &[Monkey], relief: &impl Relief, rounds: usize) -> History {
+    let mut history = Vec::with_capacity(rounds);
+    simulate_with(monkeys, relief, rounds, |inspections, monkeys| {
+        history.push(Round {
+            inspections: inspections.to_vec(),
+            items: monkeys.iter().map(|m| m.items.clone()).collect(),
+        })
+    });
+    History(history)
+}
+
+#[aoc(day11, part1)]
+fn day11_part1(monkeys: &[Monkey]) -> usize {
+    monkey_business(&simulate_with(monkeys, &DivideBy(3), 20, |_, _| {}))
+}
+
+#[aoc(day11, part2)]
+fn day11_part2(monkeys: &[Monkey]) -> usize {
+    let relief = ModProduct::new(monkeys);
+    monkey_business(&simulate_with(monkeys, &relief, 10000, |_, _| {}))
 }
 
 #[cfg(test)]
@@ -151,27 +219,30 @@ Monkey 3:
 ";
 
     fn example_monkeys() -> Vec<Monkey> {
-        use super::InspectOp::*;
-        use super::MulOp::*;
+        use super::{InspectOp, Old};
+        use crate::expr::{
+            Op::{Add, Mul},
+            Operand::{Num, Var},
+        };
         vec![
             Monkey {
                 items: vec![79, 98],
-                inspect: Mul(Imm(19)),
+                inspect: InspectOp::new(Var(Old), Mul, Num(19)),
                 check: (23, 2, 3),
             },
             Monkey {
                 items: vec![54, 65, 75, 74],
-                inspect: Add(6),
+                inspect: InspectOp::new(Var(Old), Add, Num(6)),
                 check: (19, 2, 0),
             },
             Monkey {
                 items: vec![79, 60, 97],
-                inspect: Mul(Old),
+                inspect: InspectOp::new(Var(Old), Mul, Var(Old)),
                 check: (13, 1, 3),
             },
             Monkey {
                 items: vec![74],
-                inspect: Add(3),
+                inspect: InspectOp::new(Var(Old), Add, Num(3)),
                 check: (17, 0, 1),
             },
         ]
@@ -190,4 +261,33 @@ Monkey 3:
     fn day11_part2() {
         assert_eq!(2713310158, super::day11_part2(&example_monkeys()));
     }
+
+    #[test]
+    fn day11_history() {
+        let history = super::simulate(&example_monkeys(), &super::DivideBy(3), 20);
+        assert_eq!(20, history.0.len());
+        assert_eq!(
+            vec![
+                vec![20, 23, 27, 26],
+                vec![2080, 25, 167, 207, 401, 1046],
+                vec![],
+                vec![]
+            ],
+            history.0[0].items
+        );
+        assert_eq!(vec![101, 95, 7, 105], history.0[19].inspections);
+        assert_eq!(
+            Some(&10605),
+            history
+                .monkey_business_over_time()
+                .collect::<Vec<_>>()
+                .last()
+        );
+
+        let csv = history.csv().to_string();
+        let mut lines = csv.lines();
+        assert_eq!(Some("round,monkey,inspections,items"), lines.next());
+        assert_eq!(Some("1,0,2,20 23 27 26"), lines.next());
+        assert_eq!(79, lines.count());
+    }
 }
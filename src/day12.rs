@@ -1,8 +1,8 @@
-use std::{cmp::Reverse, collections::BTreeMap};
-
 use itertools::Itertools;
 use yaah::{aoc, aoc_generator};
 
+use crate::pathfinding::a_star;
+
 pub type Map = Vec<u8>;
 
 const START: u8 = 0;
@@ -27,60 +27,6 @@ fn day12(input: &'static str) -> (Map, usize) {
 
 type Coord = usize;
 
-// reused from 2021 day 15
-fn a_star<T>(
-    origin: Coord,
-    target: Coord,
-    mut neighbours: impl FnMut(Coord) -> T,
-    mut distance: impl FnMut(Coord, Coord) -> usize,
-    mut heuristic: impl FnMut(Coord) -> usize,
-) -> Result<(usize, Vec<Coord>), ()>
-where
-    T: Iterator<Item = Coord>,
-{
-    let mut open = priority_queue::PriorityQueue::new();
-    let mut came_from: BTreeMap<Coord, Coord> = BTreeMap::new();
-
-    let mut g_score = BTreeMap::new();
-    let mut f_score = BTreeMap::new();
-
-    open.push(origin, Reverse(0));
-    g_score.insert(origin, 0);
-    f_score.insert(origin, heuristic(origin));
-
-    fn score(score_map: &BTreeMap<Coord, usize>, node: Coord) -> usize {
-        score_map.get(&node).copied().unwrap_or(usize::max_value())
-    }
-
-    while let Some((current, _)) = open.pop() {
-        if current == target {
-            // rebuild path
-            let mut current = current;
-            let mut path = vec![current];
-            while let Some(&origin) = came_from.get(&current) {
-                current = origin;
-                path.push(origin);
-            }
-            return Ok((score(&g_score, target), path.into_iter().rev().collect()));
-        }
-
-        let current_g_score = score(&g_score, current);
-        for neighbour in neighbours(current) {
-            let tentative_g_score = current_g_score + distance(current, neighbour);
-            if tentative_g_score < score(&g_score, neighbour) {
-                let tentative_f_score = tentative_g_score + heuristic(neighbour);
-
-                came_from.insert(neighbour, current);
-                g_score.insert(neighbour, tentative_g_score);
-                f_score.insert(neighbour, tentative_f_score);
-                open.push(neighbour, Reverse(tentative_f_score));
-            }
-        }
-    }
-
-    Err(())
-}
-
 fn path_len(map: &Map, size: usize, start: Coord, target: Coord) -> Option<usize> {
     let target_c = (target % size, target / size);
 
@@ -105,8 +51,7 @@ fn path_len(map: &Map, size: usize, start: Coord, target: Coord) -> Option<usize
             let (ax, ay) = (a % size, a / size);
             ax.abs_diff(target_c.0) + ay.abs_diff(target_c.1)
         },
-    )
-    .ok()?;
+    )?;
     Some(path.len() - 1)
 }
 
@@ -1,41 +1,173 @@
 use std::cmp::Ordering;
 
-use anyhow::{anyhow, Result, bail};
+use anyhow::{anyhow, Result};
 use itertools::Itertools;
 use nom::{
-    branch::alt,
-    bytes::complete::tag,
-    character::complete::digit1,
-    combinator::{map, map_res},
-    multi::separated_list0,
-    sequence::delimited,
+    character::complete::{char, digit1},
+    combinator::{all_consuming, map_res, opt, recognize},
+    error::{context, convert_error, ContextError, ParseError, VerboseError},
+    sequence::pair,
+    Finish, Offset,
 };
+use num_bigint::BigInt;
+use smallvec::{smallvec, SmallVec};
 use yaah::{aoc, aoc_generator};
 
-fn ordered(left: &Item, right: &Item) -> Ordering {
+fn cmp_integers(left: &Item, right: &Item) -> Ordering {
     use Item::*;
     match (left, right) {
         (Integer(l), Integer(r)) => l.cmp(r),
-        (List(l), List(r)) => {
-            for (left, right) in l.iter().zip(r.iter()) {
-                match ordered(left, right) {
-                    Ordering::Less => return Ordering::Less,
-                    Ordering::Equal => {}
-                    Ordering::Greater => return Ordering::Greater,
-                }
+        (Integer(l), BigInteger(r)) => BigInt::from(*l).cmp(r),
+        (BigInteger(l), Integer(r)) => l.cmp(&BigInt::from(*r)),
+        (BigInteger(l), BigInteger(r)) => l.cmp(r),
+        _ => unreachable!("lists are not integers"),
+    }
+}
+
+/// Iterates over the elements of a list, or over an integer as if it was a single element list.
+enum Cursor<'a> {
+    List(std::slice::Iter<'a, Item>),
+    Single(Option<&'a Item>),
+}
+impl<'a> Cursor<'a> {
+    fn new(item: &'a Item) -> Self {
+        match item {
+            Item::List(l) => Cursor::List(l.iter()),
+            _ => Cursor::Single(Some(item)),
+        }
+    }
+}
+impl<'a> Iterator for Cursor<'a> {
+    type Item = &'a Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Cursor::List(it) => it.next(),
+            Cursor::Single(item) => item.take(),
+        }
+    }
+}
+
+fn ordered(left: &Item, right: &Item) -> Ordering {
+    // Both packets are wrapped in a single element list so that the top level is handled like
+    // any other nesting level.
+    let mut stack: SmallVec<[(Cursor, Cursor); 16]> =
+        smallvec![(Cursor::Single(Some(left)), Cursor::Single(Some(right)))];
+
+    while let Some((l, r)) = stack.last_mut() {
+        match (l.next(), r.next()) {
+            (None, None) => {
+                stack.pop();
             }
-            l.len().cmp(&r.len())
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(l @ Item::List(_)), Some(r)) | (Some(l), Some(r @ Item::List(_))) => {
+                stack.push((Cursor::new(l), Cursor::new(r)))
+            }
+            (Some(l), Some(r)) => match cmp_integers(l, r) {
+                Ordering::Equal => {}
+                ord => return ord,
+            },
         }
-        (List(_), Integer(r)) => ordered(left, &List(vec![Integer(*r)])),
-        (Integer(l), List(_)) => ordered(&List(vec![Integer(*l)]), right),
     }
+    Ordering::Equal
 }
 
-#[derive(Debug, Clone)]
 pub enum Item {
-    Integer(usize),
+    Integer(i64),
+    /// Integers that do not fit in an `i64`.
+    BigInteger(BigInt),
     List(Vec<Item>),
 }
+impl Item {
+    /// Builds a value of the same shape as the item, mapping integers with `leaf` and gathering
+    /// the values of a list's elements with `list`.
+    ///
+    /// Nested lists are walked with an explicit stack rather than by recursing for each level.
+    fn map_nested<T>(&self, leaf: impl Fn(&Item) -> T, list: impl Fn(Vec<T>) -> T) -> T {
+        let items = match self {
+            Item::List(items) => items,
+            item => return leaf(item),
+        };
+        let mut stack = vec![(items.iter(), Vec::with_capacity(items.len()))];
+        loop {
+            let (iter, mapped) = stack.last_mut().unwrap();
+            match iter.next() {
+                Some(Item::List(l)) => stack.push((l.iter(), Vec::with_capacity(l.len()))),
+                Some(item) => mapped.push(leaf(item)),
+                None => {
+                    let (_, done) = stack.pop().unwrap();
+                    match stack.last_mut() {
+                        Some((_, parent)) => parent.push(list(done)),
+                        None => return list(done),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Writes the item with integers written by `leaf`, and lists between `open` and `close` with
+    /// their elements separated by `separator`.
+    ///
+    /// Nested lists are walked with an explicit stack rather than by recursing for each level.
+    fn write_nested(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        [open, separator, close]: [&str; 3],
+        leaf: impl Fn(&Item, &mut std::fmt::Formatter<'_>) -> std::fmt::Result,
+    ) -> std::fmt::Result {
+        let list = match self {
+            Item::List(list) => list,
+            item => return leaf(item, f),
+        };
+        f.write_str(open)?;
+        let mut stack = vec![(list.iter(), true)];
+        while let Some((iter, first)) = stack.last_mut() {
+            let Some(item) = iter.next() else {
+                stack.pop();
+                f.write_str(close)?;
+                continue;
+            };
+            if !std::mem::replace(first, false) {
+                f.write_str(separator)?;
+            }
+            match item {
+                Item::List(l) => {
+                    f.write_str(open)?;
+                    stack.push((l.iter(), true));
+                }
+                item => leaf(item, f)?,
+            }
+        }
+        Ok(())
+    }
+}
+// Nested lists are flattened before being dropped to avoid overflowing the stack on deep packets.
+impl Drop for Item {
+    fn drop(&mut self) {
+        if let Item::List(l) = self {
+            let mut stack = std::mem::take(l);
+            while let Some(mut item) = stack.pop() {
+                if let Item::List(l) = &mut item {
+                    stack.append(l);
+                }
+            }
+        }
+    }
+}
+// Cloning, printing and converting also walk nested lists with an explicit stack.
+impl Clone for Item {
+    fn clone(&self) -> Self {
+        self.map_nested(
+            |item| match item {
+                Item::Integer(v) => Item::Integer(*v),
+                Item::BigInteger(v) => Item::BigInteger(v.clone()),
+                Item::List(_) => unreachable!("lists are walked by map_nested"),
+            },
+            Item::List,
+        )
+    }
+}
 impl std::cmp::Ord for Item {
     fn cmp(&self, other: &Self) -> Ordering {
         ordered(self, other)
@@ -52,15 +184,165 @@ impl std::cmp::PartialEq for Item {
         self.cmp(other).is_eq()
     }
 }
+impl std::fmt::Display for Item {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write_nested(f, ["[", ",", "]"], |item, f| match item {
+            Item::Integer(v) => write!(f, "{v}"),
+            Item::BigInteger(v) => write!(f, "{v}"),
+            Item::List(_) => unreachable!("lists are walked by write_nested"),
+        })
+    }
+}
+/// Same output as a derived `Debug`, on a single line.
+impl std::fmt::Debug for Item {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write_nested(f, ["List([", ", ", "])"], |item, f| match item {
+            Item::Integer(v) => write!(f, "Integer({v:?})"),
+            Item::BigInteger(v) => write!(f, "BigInteger({v:?})"),
+            Item::List(_) => unreachable!("lists are walked by write_nested"),
+        })
+    }
+}
 
-fn item(input: &'static str) -> nom::IResult<&str, Item, ()> {
-    alt((
-        map(
-            delimited(tag("["), separated_list0(tag(","), item), tag("]")),
-            Item::List,
-        ),
-        map_res(digit1, |s: &str| s.parse::<usize>().map(Item::Integer)),
-    ))(input)
+// `serde_json::Value` itself drops nested arrays recursively, so very deep values may still
+// overflow the stack on their side.
+#[cfg(feature = "json")]
+impl From<&Item> for serde_json::Value {
+    fn from(item: &Item) -> Self {
+        item.map_nested(
+            |item| match item {
+                Item::Integer(v) => serde_json::Value::from(*v),
+                Item::BigInteger(v) => serde_json::Value::Number(
+                    v.to_string()
+                        .parse()
+                        .expect("arbitrary precision numbers are enabled"),
+                ),
+                Item::List(_) => unreachable!("lists are walked by map_nested"),
+            },
+            serde_json::Value::Array,
+        )
+    }
+}
+#[cfg(feature = "json")]
+impl TryFrom<&serde_json::Value> for Item {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &serde_json::Value) -> Result<Self> {
+        use serde_json::Value;
+        let leaf = |value: &Value| match value {
+            Value::Number(n) => match n.as_i64() {
+                Some(v) => Ok(Item::Integer(v)),
+                None => Ok(Item::BigInteger(
+                    n.to_string()
+                        .parse()
+                        .map_err(|_| anyhow!("not an integer: {n}"))?,
+                )),
+            },
+            _ => Err(anyhow!("unexpected json value: {value}")),
+        };
+        let values = match value {
+            Value::Array(values) => values,
+            value => return leaf(value),
+        };
+        // arrays are converted with an explicit stack, as in `Item::map_nested`.
+        let mut stack = vec![(values.iter(), Vec::with_capacity(values.len()))];
+        loop {
+            let (iter, items) = stack.last_mut().unwrap();
+            match iter.next() {
+                Some(Value::Array(a)) => stack.push((a.iter(), Vec::with_capacity(a.len()))),
+                Some(value) => items.push(leaf(value)?),
+                None => {
+                    let (_, done) = stack.pop().unwrap();
+                    match stack.last_mut() {
+                        Some((_, parent)) => parent.push(Item::List(done)),
+                        None => return Ok(Item::List(done)),
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn integer(input: &str) -> nom::IResult<&str, Item, VerboseError<&str>> {
+    context(
+        "integer",
+        map_res(recognize(pair(opt(char('-')), digit1)), |s: &str| {
+            s.parse()
+                .map(Item::Integer)
+                .or_else(|_| s.parse().map(Item::BigInteger))
+        }),
+    )(input)
+}
+
+fn in_list<'a>(e: nom::Err<VerboseError<&'a str>>, at: &'a str) -> nom::Err<VerboseError<&'a str>> {
+    // errors inside a list are not recoverable, they are reported as failures.
+    match e {
+        nom::Err::Error(e) | nom::Err::Failure(e) => {
+            nom::Err::Failure(VerboseError::add_context(at, "list", e))
+        }
+        e => e,
+    }
+}
+
+/// Parses a packet using an explicit stack of the lists being built rather than recursing for
+/// each nesting level.
+fn item(input: &str) -> nom::IResult<&str, Item, VerboseError<&str>> {
+    let mut stack: Vec<Vec<Item>> = Vec::new();
+    let mut rest = input;
+    loop {
+        // an element is expected: either a list or an integer.
+        let mut element = if let Some(r) = rest.strip_prefix('[') {
+            rest = r;
+            match rest.strip_prefix(']') {
+                Some(r) => {
+                    rest = r;
+                    Item::List(Vec::new())
+                }
+                None => {
+                    stack.push(Vec::new());
+                    continue;
+                }
+            }
+        } else {
+            match integer(rest) {
+                Ok((r, v)) => {
+                    rest = r;
+                    v
+                }
+                Err(e) if stack.is_empty() => return Err(e),
+                Err(e) => return Err(in_list(e, rest)),
+            }
+        };
+
+        // the element is complete, close as many lists as needed.
+        loop {
+            let Some(list) = stack.last_mut() else {
+                return Ok((rest, element));
+            };
+            list.push(element);
+            if let Some(r) = rest.strip_prefix(',') {
+                rest = r.trim_start_matches([' ', '\t']);
+                break;
+            }
+            match rest.strip_prefix(']') {
+                Some(r) => {
+                    rest = r;
+                    element = Item::List(stack.pop().unwrap_or_default());
+                }
+                None => {
+                    let e = VerboseError::from_char(rest, ']');
+                    return Err(in_list(nom::Err::Error(e), rest));
+                }
+            }
+        }
+    }
+}
+
+fn parse_error(input: &str, e: VerboseError<&str>) -> anyhow::Error {
+    let offset = e.errors.first().map_or(0, |&(at, _)| input.offset(at));
+    let line = input[..offset].matches('\n').count() + 1;
+    let column = offset - input[..offset].rfind('\n').map_or(0, |p| p + 1) + 1;
+    anyhow!("line {line}, column {column}:\n{}", convert_error(input, e))
 }
 
 #[aoc_generator(day13)]
@@ -70,12 +352,14 @@ fn day13(input: &'static str) -> Result<Vec<(Item, Item)>> {
         .map(|l| {
             match l
                 .lines()
-                .map(|l| item(l).map(|(_, res)| res))
+                .map(|l| all_consuming(item)(l).finish().map(|(_, res)| res))
                 .collect_tuple()
                 .ok_or(anyhow!("incomplete tuple"))?
             {
                 (Ok(left), Ok(right)) => Ok((left, right)),
-                (Err(e), _) | (_, Err(e)) => bail!(e),
+                // the error's slices point into `input` so positions are reported relative to the
+                // whole file.
+                (Err(e), _) | (_, Err(e)) => Err(parse_error(input, e)),
             }
         })
         .try_collect()
@@ -97,22 +381,39 @@ fn day13_part2(list: &[(Item, Item)]) -> usize {
 
     let mut list = list
         .iter()
-        .flat_map(|(left, right)| [left.clone(), right.clone()].into_iter())
+        .flat_map(|(left, right)| [left, right])
+        .chain([&start, &stop])
         .collect_vec();
-    list.extend_from_slice(&[start.clone(), stop.clone()]);
 
     list.sort();
     list.iter()
-        .positions(|v| {
-            let v = v.clone();
-            v == start || v == stop
-        })
+        .positions(|&v| *v == start || *v == stop)
         .map(|p| p + 1)
         .product()
 }
 
+/// Number of packets ordered strictly before `packet`, that is its 0-based position once sorted.
+pub fn rank<'a>(packets: impl IntoIterator<Item = &'a Item>, packet: &Item) -> usize {
+    packets.into_iter().filter(|&p| p < packet).count()
+}
+
+#[aoc(day13, part2, counting)]
+fn day13_part2_counting(list: &[(Item, Item)]) -> usize {
+    use Item::*;
+    let start = List(vec![List(vec![Integer(2)])]);
+    let stop = List(vec![List(vec![Integer(6)])]);
+
+    let packets = || list.iter().flat_map(|(left, right)| [left, right]);
+    // the start divider is itself ordered before the stop divider.
+    (rank(packets(), &start) + 1) * (rank(packets(), &stop) + 2)
+}
+
 #[cfg(test)]
 mod test {
+    use proptest::prelude::*;
+
+    use super::Item;
+
     const EXAMPLE: &str = r"[1,1,3,1,1]
 [1,1,5,1,1]
 
@@ -152,4 +453,152 @@ mod test {
     fn day13_part2() {
         assert_eq!(140, super::day13_part2(&super::day13(EXAMPLE).unwrap()));
     }
+
+    #[test]
+    fn day13_part2_counting() {
+        assert_eq!(
+            140,
+            super::day13_part2_counting(&super::day13(EXAMPLE).unwrap())
+        );
+    }
+
+    #[test]
+    fn day13_rank() {
+        let list = super::day13(EXAMPLE).unwrap();
+        let packets = list.iter().flat_map(|(left, right)| [left, right]);
+        let (_, packet) = super::item("[1,1,5,1,1]").unwrap();
+        assert_eq!(4, super::rank(packets.clone(), &packet));
+        let (_, packet) = super::item("[]").unwrap();
+        assert_eq!(0, super::rank(packets, &packet));
+    }
+
+    #[test]
+    fn day13_display() {
+        for line in EXAMPLE.lines().filter(|l| !l.is_empty()) {
+            let (_, item) = super::item(line).unwrap();
+            assert_eq!(line, item.to_string());
+        }
+    }
+
+    #[test]
+    fn day13_lenient_parser() {
+        let (_, item) = super::item("[1, [-2,  3], 123456789012345678901234567890]").unwrap();
+        assert_eq!(
+            "[1,[-2,3],123456789012345678901234567890]",
+            item.to_string()
+        );
+        assert!(matches!(
+            item,
+            Item::List(ref l) if matches!(l[2], Item::BigInteger(_))
+        ));
+    }
+
+    #[test]
+    fn day13_parser_error() {
+        let err = super::day13("[1,2]\n[3]\n\n[[4],5]\n[6,a]\n")
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("line 5, column 4:"), "{err}");
+    }
+
+    /// Builds a packet nested `depth` times around `leaf`, with `siblings` integers next to each
+    /// nested list.
+    fn deep_packet(depth: usize, siblings: usize, leaf: &str) -> String {
+        let mut packet = String::with_capacity(depth * (2 + 2 * siblings) + leaf.len());
+        (0..depth).for_each(|_| packet.push('['));
+        packet.push_str(leaf);
+        (0..depth).for_each(|_| {
+            (0..siblings).for_each(|s| packet.push_str(&format!(",{s}")));
+            packet.push(']');
+        });
+        packet
+    }
+
+    #[test]
+    fn day13_deep_packets() {
+        const DEPTH: usize = 100_000;
+        let parse = |s: &str| {
+            super::item(s)
+                .map(|(rest, item)| (rest.len(), item))
+                .unwrap()
+        };
+
+        let (rest, left) = parse(&deep_packet(DEPTH, 1, "1"));
+        assert_eq!(0, rest);
+        let (_, right) = parse(&deep_packet(DEPTH, 1, "2"));
+        let (_, single) = parse(&deep_packet(DEPTH, 0, "1"));
+        let (_, shallow) = parse(&deep_packet(DEPTH - 1, 0, "1"));
+
+        assert!(left < right);
+        assert!(right > left);
+        assert!(single < left);
+        // integers are promoted to lists so `[[...[1]...]]` equals `[[...1...]]`.
+        assert!(single == shallow);
+
+        let packet = deep_packet(DEPTH, 1, "1");
+        assert_eq!(packet, left.to_string());
+        assert_eq!(packet, left.clone().to_string());
+        let debug = format!("{left:?}");
+        assert!(debug.starts_with("List([List([List(["));
+        assert!(debug.contains("List([List([Integer(1), Integer(0)]), Integer(0)])"));
+        assert!(debug.ends_with("Integer(0)]), Integer(0)])"));
+        #[cfg(feature = "json")]
+        {
+            let json = serde_json::Value::from(&left);
+            let item = Item::try_from(&json).unwrap();
+            assert_eq!(packet, item.to_string());
+            // serde_json drops arrays recursively, so this one is taken apart first.
+            let mut values = vec![json];
+            while let Some(mut value) = values.pop() {
+                if let serde_json::Value::Array(a) = &mut value {
+                    values.append(a);
+                }
+            }
+        }
+        let pairs = vec![(left, right), (single, shallow)];
+        // only `right` is ordered after `[[2]]`.
+        assert_eq!(4 * 6, super::day13_part2(&pairs));
+        assert_eq!(4 * 6, super::day13_part2_counting(&pairs));
+    }
+
+    #[test]
+    fn day13_debug() {
+        let (_, item) = super::item("[1,[22,[]],123456789012345678901234567890]").unwrap();
+        assert_eq!(
+            "List([Integer(1), List([Integer(22), List([])]), \
+             BigInteger(123456789012345678901234567890)])",
+            format!("{item:?}")
+        );
+        assert_eq!("Integer(-4)", format!("{:?}", Item::Integer(-4)));
+    }
+
+    fn arb_item() -> impl Strategy<Value = Item> {
+        let leaf = prop_oneof![
+            any::<i64>().prop_map(Item::Integer),
+            "-?[1-9][0-9]{19,40}".prop_map(|s| Item::BigInteger(s.parse().unwrap())),
+        ];
+        leaf.prop_recursive(6, 64, 8, |inner| {
+            prop::collection::vec(inner, 0..8).prop_map(Item::List)
+        })
+    }
+
+    // Item's PartialEq is the packet ordering, which considers `1` and `[1]` equal, so
+    // round-trips are compared on their printed form.
+    proptest! {
+        #[test]
+        fn day13_round_trip(item in arb_item()) {
+            let printed = item.to_string();
+            let (rest, parsed) = super::item(&printed).unwrap();
+            prop_assert_eq!("", rest);
+            prop_assert_eq!(printed, parsed.to_string());
+        }
+
+        #[cfg(feature = "json")]
+        #[test]
+        fn day13_json_round_trip(item in arb_item()) {
+            let json = serde_json::Value::from(&item);
+            prop_assert_eq!(item.to_string(), json.to_string());
+            prop_assert_eq!(item.to_string(), Item::try_from(&json).unwrap().to_string());
+        }
+    }
 }
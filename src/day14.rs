@@ -1,141 +1,456 @@
-use std::collections::HashSet;
+use std::{
+    collections::HashSet,
+    num::NonZeroI32,
+    path::{Path, PathBuf},
+};
 
+use anyhow::{anyhow, bail, Result};
 use itertools::Itertools;
 use yaah::{aoc, aoc_generator};
 
-pub type Point = (i32, i32);
+use crate::{
+    point,
+    render::{Glyph, Raster},
+};
+
+pub use crate::point::Point;
 pub type Line = (Point, Point);
 
-fn draw_to_vec(lines: &[Line], width: i32, (minx, miny): (i32, i32), map: &mut [char]) {
-    lines.iter().for_each(|(start, end)| {
-        if start.1 == end.1 {
-            // draw vertically
-            let min = start.0.min(end.0);
-            let max = start.0.max(end.0);
+pub trait Cave {
+    fn is_blocked(&self, p: Point) -> bool;
+    fn block(&mut self, p: Point);
+}
+impl Cave for HashSet<Point> {
+    fn is_blocked(&self, p: Point) -> bool {
+        self.contains(&p)
+    }
+    fn block(&mut self, p: Point) {
+        self.insert(p);
+    }
+}
 
-            (min..=max).for_each(|v| map[(v - minx + width * (start.1 - miny)) as usize] = '#');
-        } else {
-            // draw vertically
-            let min = start.1.min(end.1);
-            let max = start.1.max(end.1);
+/// Bounding box of the walls and of the triangle the sand can reach when poured from `(500, 0)`
+/// onto a floor at `floor`.
+pub fn bounds(walls: &[Line], floor: i32) -> Option<(Point, Point)> {
+    if walls.is_empty() {
+        return None;
+    }
+    point::bounds(
+        walls
+            .iter()
+            .flat_map(|&(a, b)| [a, b])
+            .chain([(500 - floor, 0), (500 + floor, floor)]),
+    )
+}
 
-            (min..=max).for_each(|v| map[(start.0 - minx + width * (v - miny)) as usize] = '#');
-        }
-    });
+/// Dense bitmap of the cells of a bounded cave.
+///
+/// It only holds the walls and the cells the sand can reach when poured from `(500, 0)`, as given
+/// by [`bounds`]: simulations with other sources, or sloped floors, should use a
+/// `HashSet<Point>` instead.
+pub struct Grid {
+    origin: Point,
+    width: i32,
+    height: i32,
+    bits: Vec<u64>,
 }
-struct Map<'a>(&'a [Line]);
-impl std::fmt::Debug for Map<'_> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let (minx, maxx) = self
-            .0
-            .iter()
-            .flat_map(|v| [v.0 .0, v.1 .0].into_iter())
-            .minmax()
-            .into_option()
-            .ok_or(std::fmt::Error)?;
-        let (miny, maxy) = self
-            .0
-            .iter()
-            .flat_map(|v| [v.0 .1, v.1 .1].into_iter())
-            .minmax()
-            .into_option()
-            .ok_or(std::fmt::Error)?;
-        let width = (maxx - minx) + 1;
-        let mut map = vec!['.'; (width * (maxy - miny + 1)) as usize];
-
-        draw_to_vec(self.0, width, (minx, miny), &mut map);
-        writeln!(f)?;
-        map.chunks(width as usize)
-            .try_for_each(|line| writeln!(f, "{}", line.iter().collect::<String>()))
-    }
-}
-struct FilledMap<'a> {
-    walls: &'a [Line],
-    blocked: HashSet<Point>,
-}
-impl std::fmt::Debug for FilledMap<'_> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let (minx, maxx) = self
-            .walls
-            .iter()
-            .flat_map(|v| [v.0 .0, v.1 .0].into_iter())
-            .chain(self.blocked.iter().map(|&(x, _)| x))
-            .minmax()
-            .into_option()
-            .ok_or(std::fmt::Error)?;
-
-        let (miny, maxy) = self
-            .walls
-            .iter()
-            .flat_map(|v| [v.0 .1, v.1 .1].into_iter())
-            .chain(self.blocked.iter().map(|&(_, y)| y))
-            .minmax()
-            .into_option()
-            .ok_or(std::fmt::Error)?;
-        let width = (maxx - minx) + 1;
-        let mut map = vec!['.'; (width * (maxy - miny + 1)) as usize];
-
-        draw_to_vec(self.walls, width, (minx, miny), &mut map);
-
-        for &(x, y) in &self.blocked {
-            let cell = &mut map[(x - minx + (y - miny) * width) as usize];
-            if *cell == '.' {
-                *cell = 'o';
-            }
-        }
-        writeln!(f)?;
-        map.chunks(width as usize)
-            .try_for_each(|line| writeln!(f, "{}", line.iter().collect::<String>()))
+impl Grid {
+    pub fn new(walls: &[Line], floor: i32) -> Option<Self> {
+        let ((minx, miny), (maxx, maxy)) = bounds(walls, floor)?;
+        let width = maxx - minx + 1;
+        let height = maxy - miny + 1;
+        let mut grid = Self {
+            origin: (minx, miny),
+            width,
+            height,
+            bits: vec![0; (width * height) as usize / 64 + 1],
+        };
+        wall_points(walls).for_each(|p| grid.block(p));
+        Some(grid)
+    }
+
+    fn index(&self, (x, y): Point) -> Option<usize> {
+        let (x, y) = (x - self.origin.0, y - self.origin.1);
+        ((0..self.width).contains(&x) && (0..self.height).contains(&y))
+            .then_some((x + y * self.width) as usize)
+    }
+}
+impl Cave for Grid {
+    fn is_blocked(&self, p: Point) -> bool {
+        self.index(p)
+            .is_some_and(|idx| (self.bits[idx / 64] >> (idx % 64)) & 1 == 1)
+    }
+    fn block(&mut self, p: Point) {
+        let idx = self.index(p).expect("point outside of the cave");
+        self.bits[idx / 64] |= 1 << (idx % 64);
     }
 }
 
+/// Rasterises a segment with Bresenham's algorithm, yielding both of its ends.
+pub fn rasterise(&(start, end): &Line) -> impl Iterator<Item = Point> {
+    let (dx, dy) = ((end.0 - start.0).abs(), -(end.1 - start.1).abs());
+    let (sx, sy) = ((end.0 - start.0).signum(), (end.1 - start.1).signum());
+    let mut err = dx + dy;
+    let mut current = Some(start);
+    std::iter::from_fn(move || {
+        let point = current?;
+        current = (point != end).then(|| {
+            let (mut x, mut y) = point;
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+            (x, y)
+        });
+        Some(point)
+    })
+}
+
+fn wall_points(walls: &[Line]) -> impl Iterator<Item = Point> + '_ {
+    walls.iter().flat_map(rasterise)
+}
+
 fn init_blocked(walls: &[Line]) -> HashSet<Point> {
+    wall_points(walls).collect()
+}
+
+fn max_y(walls: &[Line]) -> Option<i32> {
     walls
         .iter()
-        .flat_map(|(start, end)| {
-            if start.1 == end.1 {
-                // draw vertically
-                let min = start.0.min(end.0);
-                let max = start.0.max(end.0);
+        .flat_map(|v| [v.0 .1, v.1 .1].into_iter())
+        .max()
+}
 
-                either::Either::Left((min..=max).map(|v| (v, start.1)))
-            } else {
-                // draw vertically
-                let min = start.1.min(end.1);
-                let max = start.1.max(end.1);
+/// How [`parse`] handles segments that are neither horizontal nor vertical.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Diagonals {
+    Reject,
+    Rasterise,
+}
 
-                either::Either::Right((min..=max).map(|v| (start.0, v)))
+pub fn parse(input: &str, diagonals: Diagonals) -> Result<Vec<Line>> {
+    let mut walls = Vec::new();
+    for (n, l) in input.lines().enumerate().filter(|(_, l)| !l.is_empty()) {
+        let points: Vec<Point> = l
+            .split(" -> ")
+            .map(|point| {
+                point
+                    .split(',')
+                    .map(|v| v.trim().parse().ok())
+                    .collect_tuple()
+                    .and_then(|(x, y)| Some((x?, y?)))
+                    .ok_or_else(|| anyhow!("line {}: invalid point `{point}`: `{l}`", n + 1))
+            })
+            .try_collect()?;
+        for (start, end) in points.into_iter().tuple_windows() {
+            if diagonals == Diagonals::Reject && start.0 != end.0 && start.1 != end.1 {
+                bail!(
+                    "line {}: segment {start:?} -> {end:?} is not horizontal or vertical: `{l}`",
+                    n + 1
+                );
             }
-        })
-        .collect()
+            walls.push((start, end));
+        }
+    }
+    Ok(walls)
 }
 
 #[aoc_generator(day14)]
-fn gen(input: &'static str) -> Vec<Line> {
-    input
-        .lines()
-        .flat_map(|l| {
-            l.split(" -> ")
-                .filter_map(|point| {
-                    point
-                        .split(',')
-                        .filter_map(|v| v.parse().ok())
-                        .collect_tuple()
-                })
-                .tuple_windows()
+pub fn gen(input: &'static str) -> Result<Vec<Line>> {
+    parse(input, Diagonals::Reject)
+}
+
+/// Default fall order: down, down-left then down-right.
+pub const FALL: [Point; 3] = [(0, 1), (-1, 1), (1, 1)];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Floor {
+    /// Horizontal floor at the given depth.
+    Flat(i32),
+    /// Floor at `origin` going one unit deeper every `run` columns to the right.
+    ///
+    /// Grains can slide down it forever if `run` is too short, see [`SandSim::run`].
+    Sloped { origin: Point, run: NonZeroI32 },
+}
+impl Floor {
+    fn blocks(&self, (x, y): Point) -> bool {
+        match *self {
+            Floor::Flat(depth) => y >= depth,
+            Floor::Sloped { origin, run } => y >= origin.1 + (x - origin.0).div_euclid(run.get()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stop {
+    /// Stops as soon as a grain falls deeper than the given row.
+    Abyss(i32),
+    /// Stops once every source is blocked, which requires a floor for the simulation to end.
+    SourcesBlocked,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SandSim {
+    pub sources: Vec<Point>,
+    pub floor: Option<Floor>,
+    /// Offsets tried in order to move a grain, it comes to rest if none is free.
+    pub directions: Vec<Point>,
+    pub stop: Stop,
+}
+impl SandSim {
+    pub fn part1(maxy: i32) -> Self {
+        Self {
+            sources: vec![(500, 0)],
+            floor: None,
+            directions: FALL.to_vec(),
+            stop: Stop::Abyss(maxy),
+        }
+    }
+
+    pub fn part2(maxy: i32) -> Self {
+        Self {
+            sources: vec![(500, 0)],
+            floor: Some(Floor::Flat(maxy + 2)),
+            directions: FALL.to_vec(),
+            stop: Stop::SourcesBlocked,
+        }
+    }
+
+    fn is_free(&self, blocked: &impl Cave, p: Point) -> bool {
+        !blocked.is_blocked(p) && !self.floor.is_some_and(|floor| floor.blocks(p))
+    }
+
+    /// Checks that the simulation ends: grains must always move down, and with
+    /// [`Stop::SourcesBlocked`] they must come to rest on a floor they cannot slide along forever.
+    fn check(&self) -> Result<()> {
+        if let Some(direction) = self.directions.iter().find(|d| d.1 <= 0) {
+            bail!("direction {direction:?} does not move grains down");
+        }
+        match (self.stop, self.floor) {
+            (Stop::SourcesBlocked, None) => bail!("sources are never blocked without a floor"),
+            (Stop::SourcesBlocked, Some(Floor::Sloped { run, .. })) => {
+                // the floor sinks by one row every `run` columns, grains must sink faster.
+                if let Some(direction) = self
+                    .directions
+                    .iter()
+                    .find(|&&(dx, dy)| run.get().abs() * dy <= dx.abs())
+                {
+                    bail!("grains moving by {direction:?} slide along a floor with a run of {run}");
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Drops one grain from each source in turn and returns the number of grains at rest once
+    /// the stop condition is met.
+    ///
+    /// Each source remembers the path of its previous grain so the next one resumes from the last
+    /// position that was still free instead of replaying its whole fall.
+    ///
+    /// Fails without running if the simulation would never end, e.g. with
+    /// [`Stop::SourcesBlocked`] and no floor.
+    pub fn run(&self, blocked: &mut impl Cave) -> Result<usize> {
+        self.run_with(blocked, |_, _| {})
+    }
+
+    /// Same as [`SandSim::run`], calling `on_rest` with the cave and the number of grains at rest
+    /// every time a grain comes to rest.
+    pub fn run_with<C: Cave>(
+        &self,
+        blocked: &mut C,
+        mut on_rest: impl FnMut(&C, usize),
+    ) -> Result<usize> {
+        self.check()?;
+        let mut units = 0;
+        let mut paths = self.sources.iter().map(|&s| vec![s]).collect_vec();
+
+        'outer: loop {
+            let mut settled = false;
+            for path in &mut paths {
+                if self.sources.len() > 1 {
+                    // grains from other sources may have settled on this path.
+                    if let Some(idx) = path.iter().position(|&p| !self.is_free(blocked, p)) {
+                        path.truncate(idx);
+                    }
+                }
+
+                while let Some(&unit) = path.last() {
+                    if matches!(self.stop, Stop::Abyss(maxy) if unit.1 > maxy) {
+                        // falling off the edge, stop simulation
+                        break 'outer;
+                    }
+
+                    let next = self
+                        .directions
+                        .iter()
+                        .map(|d| (unit.0 + d.0, unit.1 + d.1))
+                        .find(|&p| self.is_free(blocked, p));
+                    match next {
+                        Some(next) => path.push(next),
+                        None => {
+                            blocked.block(unit);
+                            units += 1;
+                            settled = true;
+                            on_rest(blocked, units);
+                            // the next grain follows the same path up to the previous position.
+                            path.pop();
+                            break;
+                        }
+                    }
+                }
+            }
+            if !settled {
+                // all sources are blocked
+                break;
+            }
+        }
+        Ok(units)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cell {
+    Air,
+    Rock,
+    Sand,
+}
+impl Glyph for Cell {
+    fn symbol(self) -> char {
+        match self {
+            Cell::Air => '.',
+            Cell::Rock => '#',
+            Cell::Sand => 'o',
+        }
+    }
+    fn color(self) -> [u8; 3] {
+        match self {
+            Cell::Air => [16, 16, 24],
+            Cell::Rock => [128, 128, 128],
+            Cell::Sand => [230, 190, 90],
+        }
+    }
+}
+
+/// Snapshot of a rectangular region of the cave.
+pub type Frame = Raster<Cell>;
+
+pub fn frame(
+    walls: &[Line],
+    blocked: &impl Cave,
+    floor: Option<Floor>,
+    ((minx, miny), (maxx, maxy)): (Point, Point),
+) -> Frame {
+    let rocks: HashSet<Point> = wall_points(walls).collect();
+    let cells = (miny..=maxy)
+        .cartesian_product(minx..=maxx)
+        .map(|(y, x)| {
+            if rocks.contains(&(x, y)) || floor.is_some_and(|floor| floor.blocks((x, y))) {
+                Cell::Rock
+            } else if blocked.is_blocked((x, y)) {
+                Cell::Sand
+            } else {
+                Cell::Air
+            }
         })
-        .collect_vec()
+        .collect();
+    Raster::new((maxx - minx + 1) as usize, cells)
 }
 
-#[aoc(day14, part1)]
-fn day14_part1(walls: &[Line]) -> Option<usize> {
-    let maxy = walls
-        .iter()
-        .flat_map(|v| [v.0 .1, v.1 .1].into_iter())
-        .max()?;
+/// Image format of recorded frames.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Ppm,
+    #[cfg(feature = "png")]
+    Png,
+}
+impl Format {
+    const ALL: &'static [Format] = &[
+        Format::Ppm,
+        #[cfg(feature = "png")]
+        Format::Png,
+    ];
 
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Ppm => "ppm",
+            #[cfg(feature = "png")]
+            Format::Png => "png",
+        }
+    }
+}
+
+/// Writes frames of a simulation as it runs.
+pub struct Recorder {
+    pub dir: PathBuf,
+    /// A frame is written every `every` grains at rest, and once the simulation ends.
+    pub every: usize,
+    pub scale: usize,
+    pub format: Format,
+}
+impl Recorder {
+    fn write(&self, frame: Frame, units: usize) -> anyhow::Result<()> {
+        let name = format!("frame_{units:08}.{}", self.format.extension());
+        let file = std::io::BufWriter::new(std::fs::File::create(self.dir.join(name))?);
+        match self.format {
+            Format::Ppm => frame.write_ppm(file, self.scale)?,
+            #[cfg(feature = "png")]
+            Format::Png => frame.write_png(file, self.scale)?,
+        }
+        Ok(())
+    }
+
+    /// Runs `sim` over `blocked`, rendering the region `bounds` of the cave, and returns the
+    /// number of grains at rest.
+    pub fn record<C: Cave>(
+        &self,
+        sim: &SandSim,
+        walls: &[Line],
+        blocked: &mut C,
+        bounds: (Point, Point),
+    ) -> anyhow::Result<usize> {
+        std::fs::create_dir_all(&self.dir)?;
+        // frames of a previous recording would be mixed with this one's.
+        for frame in self.frames()? {
+            std::fs::remove_file(frame)?;
+        }
+        let mut res = Ok(());
+        let units = sim.run_with(blocked, |blocked, units| {
+            if units.is_multiple_of(self.every) && res.is_ok() {
+                res = self.write(frame(walls, blocked, sim.floor, bounds), units);
+            }
+        })?;
+        res?;
+        if !units.is_multiple_of(self.every) {
+            self.write(frame(walls, blocked, sim.floor, bounds), units)?;
+        }
+        Ok(units)
+    }
+
+    pub fn frames(&self) -> std::io::Result<impl Iterator<Item = PathBuf>> {
+        Ok(std::fs::read_dir(&self.dir)?
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| is_frame(path))
+            .sorted())
+    }
+}
+
+fn is_frame(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.strip_prefix("frame_"))
+        .and_then(|name| name.rsplit_once('.'))
+        .is_some_and(|(_, ext)| Format::ALL.iter().any(|f| f.extension() == ext))
+}
+
+fn fill_part1(blocked: &mut impl Cave, maxy: i32) -> usize {
     let mut units = 0;
-    let mut blocked = init_blocked(walls);
 
     'outer: loop {
         let mut unit = (500, 0);
@@ -146,69 +461,55 @@ fn day14_part1(walls: &[Line]) -> Option<usize> {
             }
 
             let down = (unit.0, unit.1 + 1);
-            if !blocked.contains(&down) {
+            if !blocked.is_blocked(down) {
                 unit = down;
                 continue;
             }
             let down_left = (unit.0 - 1, down.1);
-            if !blocked.contains(&down_left) {
+            if !blocked.is_blocked(down_left) {
                 unit = down_left;
                 continue;
             }
             let down_right = (down_left.0 + 2, down_left.1);
-            if !blocked.contains(&down_right) {
+            if !blocked.is_blocked(down_right) {
                 unit = down_right;
                 continue;
             }
 
-            blocked.insert(unit);
+            blocked.block(unit);
             units += 1;
             // particle blocked, introduce a new one.
             break;
         }
     }
-
-    #[cfg(test)]
-    {
-        println!("Filled:");
-        println!("{:?}", FilledMap { walls, blocked });
-    }
-
-    Some(units)
+    units
 }
 
-#[aoc(day14, part2)]
-fn day14_part2(walls: &[Line]) -> Option<usize> {
-    let maxy = walls
-        .iter()
-        .flat_map(|v| [v.0 .1, v.1 .1].into_iter())
-        .max()?;
-
+fn fill_part2(blocked: &mut impl Cave, maxy: i32) -> usize {
     let mut units = 0;
-    let mut blocked = init_blocked(walls);
 
     'outer: loop {
         let mut unit = (500, 0);
         loop {
             if unit.1 < (maxy + 1) {
                 let down = (unit.0, unit.1 + 1);
-                if !blocked.contains(&down) {
+                if !blocked.is_blocked(down) {
                     unit = down;
                     continue;
                 }
                 let down_left = (unit.0 - 1, down.1);
-                if !blocked.contains(&down_left) {
+                if !blocked.is_blocked(down_left) {
                     unit = down_left;
                     continue;
                 }
                 let down_right = (down_left.0 + 2, down_left.1);
-                if !blocked.contains(&down_right) {
+                if !blocked.is_blocked(down_right) {
                     unit = down_right;
                     continue;
                 }
             }
 
-            blocked.insert(unit);
+            blocked.block(unit);
             units += 1;
 
             if unit == (500, 0) {
@@ -219,18 +520,93 @@ fn day14_part2(walls: &[Line]) -> Option<usize> {
             break;
         }
     }
+    units
+}
+
+#[aoc(day14, part1)]
+fn day14_part1(walls: &[Line]) -> Option<usize> {
+    let maxy = max_y(walls)?;
+    let mut blocked = Grid::new(walls, maxy + 2)?;
+    let units = SandSim::part1(maxy).run(&mut blocked).ok()?;
 
     #[cfg(test)]
     {
         println!("Filled:");
-        println!("{:?}", FilledMap { walls, blocked });
+        println!("{}", frame(walls, &blocked, None, bounds(walls, maxy)?));
     }
 
     Some(units)
 }
 
+#[aoc(day14, part1, hashset)]
+fn day14_part1_hashset(walls: &[Line]) -> Option<usize> {
+    let maxy = max_y(walls)?;
+    SandSim::part1(maxy).run(&mut init_blocked(walls)).ok()
+}
+
+#[aoc(day14, part1, replay)]
+fn day14_part1_replay(walls: &[Line]) -> Option<usize> {
+    let maxy = max_y(walls)?;
+    Some(fill_part1(&mut Grid::new(walls, maxy + 2)?, maxy))
+}
+
+#[aoc(day14, part2)]
+fn day14_part2(walls: &[Line]) -> Option<usize> {
+    let maxy = max_y(walls)?;
+    let mut blocked = Grid::new(walls, maxy + 2)?;
+    let units = SandSim::part2(maxy).run(&mut blocked).ok()?;
+
+    #[cfg(test)]
+    {
+        println!("Filled:");
+        println!("{}", frame(walls, &blocked, None, bounds(walls, maxy + 1)?));
+    }
+
+    Some(units)
+}
+
+#[aoc(day14, part2, hashset)]
+fn day14_part2_hashset(walls: &[Line]) -> Option<usize> {
+    let maxy = max_y(walls)?;
+    SandSim::part2(maxy).run(&mut init_blocked(walls)).ok()
+}
+
+#[aoc(day14, part2, replay)]
+fn day14_part2_replay(walls: &[Line]) -> Option<usize> {
+    let maxy = max_y(walls)?;
+    Some(fill_part2(&mut Grid::new(walls, maxy + 2)?, maxy))
+}
+
+/// Counts the cells reachable from the source row by row: a cell is filled if it is not a rock
+/// and any of the three cells above it is filled.
+#[aoc(day14, part2, rows)]
+fn day14_part2_rows(walls: &[Line]) -> Option<usize> {
+    let maxy = max_y(walls)?;
+    let rocks = Grid::new(walls, maxy + 2)?;
+    let minx = 500 - (maxy + 1);
+    let width = (2 * (maxy + 1) + 1) as usize;
+
+    let mut row = vec![false; width];
+    row[(500 - minx) as usize] = true;
+    let mut units = 1;
+    for y in 1..=(maxy + 1) {
+        row = (0..width)
+            .map(|i| {
+                !rocks.is_blocked((minx + i as i32, y))
+                    && row[i.saturating_sub(1)..(i + 2).min(width)]
+                        .iter()
+                        .any(|&v| v)
+            })
+            .collect();
+        units += row.iter().filter(|&&v| v).count();
+    }
+    Some(units)
+}
+
 #[cfg(test)]
 mod test {
+    use std::collections::HashSet;
+
     const EXAMPLE: &str = r"498,4 -> 498,6 -> 496,6
 503,4 -> 502,4 -> 502,9 -> 494,9";
 
@@ -243,16 +619,246 @@ mod test {
             ((502, 4), (502, 9)),
             ((502, 9), (494, 9)),
         ];
-        assert_eq!(expected, super::gen(EXAMPLE));
-        println!("{:?}", super::Map(&expected));
+        assert_eq!(expected, super::gen(EXAMPLE).unwrap());
+        let bounds = ((494, 4), (503, 9));
+        println!("{}", super::frame(&expected, &HashSet::new(), None, bounds));
+    }
+
+    #[test]
+    fn day14_invalid_walls() {
+        let err = super::gen("498,4 -> 498,6\n503,4 -> 502,5").unwrap_err();
+        assert_eq!(
+            "line 2: segment (503, 4) -> (502, 5) is not horizontal or vertical: `503,4 -> 502,5`",
+            err.to_string()
+        );
+        let err = super::gen("498,4 -> 498,x").unwrap_err();
+        assert_eq!(
+            "line 1: invalid point `498,x`: `498,4 -> 498,x`",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn day14_diagonal_walls() {
+        use super::{parse, rasterise, Diagonals};
+        let walls = parse("0,0 -> 3,3 -> 3,1 -> 0,2", Diagonals::Rasterise).unwrap();
+        assert_eq!(
+            vec![
+                vec![(0, 0), (1, 1), (2, 2), (3, 3)],
+                vec![(3, 3), (3, 2), (3, 1)],
+                vec![(3, 1), (2, 1), (1, 2), (0, 2)],
+            ],
+            walls
+                .iter()
+                .map(|w| rasterise(w).collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn day14_frame() {
+        let walls = super::gen(EXAMPLE).unwrap();
+        let mut blocked = super::init_blocked(&walls);
+        super::SandSim::part1(9).run(&mut blocked).unwrap();
+        let frame = super::frame(&walls, &blocked, None, ((493, 0), (504, 10)));
+        let expected = r"
+............
+............
+.......o....
+......ooo...
+.....#ooo##.
+....o#ooo#..
+...###ooo#..
+.....oooo#..
+..o.ooooo#..
+.#########..
+............
+";
+        assert_eq!(expected, frame.to_string());
+
+        let mut ppm = Vec::new();
+        frame.write_ppm(&mut ppm, 2).unwrap();
+        let header = b"P6\n24 22\n255\n";
+        assert_eq!(&header[..], &ppm[..header.len()]);
+        assert_eq!(header.len() + 24 * 22 * 3, ppm.len());
+
+        #[cfg(feature = "png")]
+        {
+            let mut png = Vec::new();
+            frame.write_png(&mut png, 2).unwrap();
+            assert_eq!(b"\x89PNG", &png[..4]);
+        }
+    }
+
+    #[test]
+    fn day14_recorder() {
+        let walls = super::gen(EXAMPLE).unwrap();
+        let recorder = super::Recorder {
+            dir: std::env::temp_dir().join("aoc_day14_recorder"),
+            every: 5,
+            scale: 1,
+            format: super::Format::Ppm,
+        };
+        std::fs::create_dir_all(&recorder.dir).unwrap();
+        // left over by an earlier recording.
+        std::fs::write(recorder.dir.join("frame_00000099.ppm"), "").unwrap();
+        let units = recorder
+            .record(
+                &super::SandSim::part1(9),
+                &walls,
+                &mut super::init_blocked(&walls),
+                super::bounds(&walls, 9).unwrap(),
+            )
+            .unwrap();
+        assert_eq!(24, units);
+        let frames = recorder
+            .frames()
+            .unwrap()
+            .filter_map(|p| Some(p.file_name()?.to_str()?.to_owned()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                "frame_00000005.ppm",
+                "frame_00000010.ppm",
+                "frame_00000015.ppm",
+                "frame_00000020.ppm",
+                "frame_00000024.ppm",
+            ],
+            frames
+        );
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn day14_recorder_png() {
+        let walls = super::gen(EXAMPLE).unwrap();
+        let recorder = super::Recorder {
+            dir: std::env::temp_dir().join("aoc_day14_recorder_png"),
+            every: 10,
+            scale: 2,
+            format: super::Format::Png,
+        };
+        recorder
+            .record(
+                &super::SandSim::part1(9),
+                &walls,
+                &mut super::init_blocked(&walls),
+                super::bounds(&walls, 9).unwrap(),
+            )
+            .unwrap();
+        let frames = recorder.frames().unwrap().collect::<Vec<_>>();
+        assert_eq!(3, frames.len());
+        assert!(frames.iter().all(|f| f.extension().unwrap() == "png"));
+        assert!(std::fs::read(&frames[0]).unwrap().starts_with(b"\x89PNG"));
     }
 
     #[test]
     fn day14_part1() {
-        assert_eq!(Some(24), super::day14_part1(&super::gen(EXAMPLE)));
+        assert_eq!(Some(24), super::day14_part1(&super::gen(EXAMPLE).unwrap()));
     }
     #[test]
     fn day14_part2() {
-        assert_eq!(Some(93), super::day14_part2(&super::gen(EXAMPLE)));
+        assert_eq!(Some(93), super::day14_part2(&super::gen(EXAMPLE).unwrap()));
+    }
+    #[test]
+    fn day14_part1_hashset() {
+        assert_eq!(
+            Some(24),
+            super::day14_part1_hashset(&super::gen(EXAMPLE).unwrap())
+        );
+    }
+    #[test]
+    fn day14_part2_hashset() {
+        assert_eq!(
+            Some(93),
+            super::day14_part2_hashset(&super::gen(EXAMPLE).unwrap())
+        );
+    }
+    #[test]
+    fn day14_sources() {
+        use super::{Floor, SandSim, Stop, FALL};
+        let mut sim = SandSim {
+            sources: vec![(500, 0), (510, 0)],
+            floor: Some(Floor::Flat(3)),
+            directions: FALL.to_vec(),
+            stop: Stop::SourcesBlocked,
+        };
+        // two disjoint triangles of 3 rows.
+        assert_eq!(18, sim.run(&mut std::collections::HashSet::new()).unwrap());
+
+        // overlapping sources share the same pile.
+        sim.sources = vec![(500, 0), (500, 0)];
+        sim.floor = Some(Floor::Flat(11));
+        assert_eq!(
+            93,
+            sim.run(&mut super::init_blocked(&super::gen(EXAMPLE).unwrap()))
+                .unwrap()
+        );
+    }
+    #[test]
+    fn day14_sloped_floor() {
+        use super::{Floor, SandSim, Stop, FALL};
+        let mut sim = SandSim {
+            sources: vec![(500, 0)],
+            floor: Some(Floor::Sloped {
+                origin: (500, 2),
+                run: std::num::NonZeroI32::new(2).unwrap(),
+            }),
+            directions: FALL.to_vec(),
+            stop: Stop::SourcesBlocked,
+        };
+        // the floor deepens every other column so grains roll down it on the right.
+        // .o..
+        // #oo.
+        // ###o
+        assert_eq!(4, sim.run(&mut std::collections::HashSet::new()).unwrap());
+
+        // as steep as the grains' fall, they would slide down it forever.
+        for run in [1, -1] {
+            sim.floor = Some(Floor::Sloped {
+                origin: (500, 2),
+                run: std::num::NonZeroI32::new(run).unwrap(),
+            });
+            assert!(sim.run(&mut std::collections::HashSet::new()).is_err());
+        }
+        // it is fine once grains fall off the edge.
+        sim.stop = Stop::Abyss(20);
+        assert_eq!(0, sim.run(&mut std::collections::HashSet::new()).unwrap());
+    }
+    #[test]
+    fn day14_endless_sims() {
+        use super::{SandSim, Stop, FALL};
+        let mut sim = SandSim::part2(9);
+        sim.floor = None;
+        let err = sim.run(&mut std::collections::HashSet::new()).unwrap_err();
+        assert_eq!("sources are never blocked without a floor", err.to_string());
+
+        let mut sim = SandSim::part1(9);
+        sim.directions = [&FALL[..], &[(1, 0)]].concat();
+        assert!(sim.run(&mut std::collections::HashSet::new()).is_err());
+        sim.stop = Stop::SourcesBlocked;
+        sim.directions = vec![(0, -1)];
+        assert!(sim.run(&mut std::collections::HashSet::new()).is_err());
+    }
+    #[test]
+    fn day14_part1_replay() {
+        assert_eq!(
+            Some(24),
+            super::day14_part1_replay(&super::gen(EXAMPLE).unwrap())
+        );
+    }
+    #[test]
+    fn day14_part2_replay() {
+        assert_eq!(
+            Some(93),
+            super::day14_part2_replay(&super::gen(EXAMPLE).unwrap())
+        );
+    }
+    #[test]
+    fn day14_part2_rows() {
+        assert_eq!(
+            Some(93),
+            super::day14_part2_rows(&super::gen(EXAMPLE).unwrap())
+        );
     }
 }
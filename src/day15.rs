@@ -1,13 +1,84 @@
 use std::collections::HashSet;
 
+use anyhow::{anyhow, bail, Result};
 use itertools::Itertools;
 use rayon::prelude::*;
 use yaah::{aoc, aoc_generator};
 
-pub type Coord = (i64, i64);
+use crate::{
+    interval::IntervalSet,
+    render::{Glyph, Raster},
+};
+
+pub use crate::point::Coord;
+
+/// Puzzle parameters that differ between the example and the real input.
+///
+/// They can be overridden by header lines of `key=value` pairs before the report, e.g.
+/// `row=10, area=20`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Params {
+    /// Row checked in part 1.
+    pub row: i64,
+    /// The distress beacon is searched for in `0..=search_area` along both axes.
+    pub search_area: i64,
+    /// Multiplier of `x` in the tuning frequency.
+    pub tuning: i64,
+}
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            row: 2_000_000,
+            search_area: 4_000_000,
+            tuning: 4_000_000,
+        }
+    }
+}
+impl Params {
+    fn set(&mut self, header: &str) -> Result<()> {
+        header
+            .split(',')
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .try_for_each(|pair| {
+                let (key, value) = pair
+                    .split_once('=')
+                    .ok_or_else(|| anyhow!("expected `key=value`, got `{pair}`"))?;
+                let value = value.trim().parse()?;
+                match key.trim() {
+                    "row" => self.row = value,
+                    "area" => self.search_area = value,
+                    "tuning" => self.tuning = value,
+                    key => bail!("unknown parameter `{key}`"),
+                }
+                Ok(())
+            })
+    }
+
+    fn tuning_frequency(&self, (x, y): Coord) -> i64 {
+        x * self.tuning + y
+    }
+}
+
+pub struct Report {
+    pub params: Params,
+    pub sensors: Vec<(Coord, Coord)>,
+}
 
 #[aoc_generator(day15)]
-fn day15(input: &'static str) -> Vec<(Coord, Coord)> {
+fn day15(input: &str) -> Result<Report> {
+    let mut params = Params::default();
+    input
+        .lines()
+        .take_while(|l| !l.starts_with("Sensor"))
+        .try_for_each(|l| params.set(l))?;
+    Ok(Report {
+        params,
+        sensors: parse_sensors(input),
+    })
+}
+
+fn parse_sensors(input: &str) -> Vec<(Coord, Coord)> {
     let re = regex::Regex::new(r"Sensor at x=(?P<sx>-?\d+), y=(?P<sy>-?\d+): closest beacon is at x=(?P<bx>-?\d+), y=(?P<by>-?\d+)").unwrap();
     re.captures_iter(input)
         .filter_map(|caps| {
@@ -21,15 +92,20 @@ fn day15(input: &'static str) -> Vec<(Coord, Coord)> {
         .collect()
 }
 
-fn check_line(input: &[(Coord, Coord)], y: i64) -> usize {
-    let beacons: HashSet<Coord> = input.iter().map(|&(_, beacon)| beacon).collect();
-    let sensor_ranges: Vec<(Coord, i64)> = input
+/// Each sensor along with the distance to its beacon, within which there is no other beacon.
+fn sensor_ranges(input: &[(Coord, Coord)]) -> Vec<(Coord, i64)> {
+    input
         .iter()
         .map(|&(sensor, beacon)| {
             let effective_range = (sensor.0 - beacon.0).abs() + (sensor.1 - beacon.1).abs();
             (sensor, effective_range)
         })
-        .collect();
+        .collect()
+}
+
+fn check_line(input: &[(Coord, Coord)], y: i64) -> usize {
+    let beacons: HashSet<Coord> = input.iter().map(|&(_, beacon)| beacon).collect();
+    let sensor_ranges = sensor_ranges(input);
 
     let (min_x, max_x) = input
         .iter()
@@ -56,45 +132,232 @@ fn check_line(input: &[(Coord, Coord)], y: i64) -> usize {
         .count()
 }
 
-fn find_distress_beacon(input: &[(Coord, Coord)], search_area: i64) -> Option<i64> {
-    let sensor_ranges: Vec<(Coord, i64)> = input
+/// Points of the row `y` covered by at least one sensor.
+fn covered_spans(input: &[(Coord, Coord)], y: i64) -> IntervalSet<i64> {
+    row_coverage(&sensor_ranges(input), y)
+}
+
+/// Points of the row `y` within range of one of `sensor_ranges`.
+fn row_coverage(sensor_ranges: &[(Coord, i64)], y: i64) -> IntervalSet<i64> {
+    sensor_ranges
         .iter()
-        .map(|&(sensor, beacon)| {
-            let effective_range = (sensor.0 - beacon.0).abs() + (sensor.1 - beacon.1).abs();
-            (sensor, effective_range)
+        .map(|&(sensor, range)| {
+            let half_width = range - (sensor.1 - y).abs();
+            (sensor.0 - half_width)..=(sensor.0 + half_width)
         })
-        .collect();
+        .collect()
+}
 
-    (0..=search_area)
-        .into_par_iter()
-        .find_map_any(|y| {
-            let mut x = 0;
-            'outer: loop {
-                for &(coord, range) in &sensor_ranges {
-                    let dist = (coord.0 - x).abs() + (coord.1 - y).abs();
-                    if dist <= range {
-                        x = coord.0 + (range - (coord.1 - y).abs()) + 1;
-                        continue 'outer;
-                    }
+fn check_line_intervals(input: &[(Coord, Coord)], y: i64) -> usize {
+    let spans = covered_spans(input, y);
+    let beacons = input
+        .iter()
+        .filter(|&&(_, (bx, by))| by == y && spans.contains(bx))
+        .map(|&(_, beacon)| beacon)
+        .unique()
+        .count();
+    spans.len() as usize - beacons
+}
+
+fn find_distress_beacon(input: &[(Coord, Coord)], search_area: i64) -> Option<Coord> {
+    let sensor_ranges = sensor_ranges(input);
+
+    (0..=search_area).into_par_iter().find_map_any(|y| {
+        let mut x = 0;
+        'outer: loop {
+            for &(coord, range) in &sensor_ranges {
+                let dist = (coord.0 - x).abs() + (coord.1 - y).abs();
+                if dist <= range {
+                    x = coord.0 + (range - (coord.1 - y).abs()) + 1;
+                    continue 'outer;
                 }
-                break;
             }
-            (x <= search_area).then_some((x, y))
+            break;
+        }
+        (x <= search_area).then_some((x, y))
+    })
+}
+
+/// First uncovered point of the search area, in `(x, y)` order, assuming uncovered points are
+/// isolated.
+///
+/// In coordinates rotated by 45° (`u = x + y`, `v = x - y`) the points just outside a sensor's
+/// range lie on two `u` and two `v` lines. An isolated uncovered point is surrounded by covered
+/// ones so it lies on such lines: either at the intersection of a `u` and a `v` line, or where
+/// one of them meets the edge of the search area, or at one of its corners. Only those points are
+/// checked, so larger uncovered regions may be missed: see [`uncovered_rows`] to find them all.
+fn find_isolated_beacon(input: &[(Coord, Coord)], search_area: i64) -> Option<Coord> {
+    let sensor_ranges = sensor_ranges(input);
+
+    let (u_lines, v_lines): (Vec<i64>, Vec<i64>) = sensor_ranges
+        .iter()
+        .flat_map(|&((sx, sy), range)| {
+            [-1, 1].map(|side| (sx + sy + side * (range + 1), sx - sy + side * (range + 1)))
         })
-        .map(|(x, y)| x * 4_000_000 + y)
+        .unzip();
+
+    let intersections = u_lines
+        .iter()
+        .cartesian_product(v_lines.iter())
+        .filter(|&(u, v)| (u + v) % 2 == 0)
+        .map(|(u, v)| ((u + v) / 2, (u - v) / 2));
+    let edges = [0, search_area].into_iter().flat_map(|edge| {
+        u_lines
+            .iter()
+            .flat_map(move |u| [(edge, u - edge), (u - edge, edge)])
+            .chain(
+                v_lines
+                    .iter()
+                    .flat_map(move |v| [(edge, edge - v), (v + edge, edge)]),
+            )
+    });
+    let corners = [0, search_area]
+        .into_iter()
+        .cartesian_product([0, search_area]);
+
+    intersections
+        .chain(edges)
+        .chain(corners)
+        .filter(|&(x, y)| (0..=search_area).contains(&x) && (0..=search_area).contains(&y))
+        .filter(|&(x, y)| {
+            sensor_ranges
+                .iter()
+                .all(|&(coord, range)| (coord.0 - x).abs() + (coord.1 - y).abs() > range)
+        })
+        .min()
+}
+
+/// Every point of the search area not covered by any sensor, as the uncovered spans of each row
+/// holding some.
+///
+/// Unlike [`find_isolated_beacon`] this finds uncovered regions of any shape, at the cost of
+/// going through every row of the area.
+pub fn uncovered_rows(input: &[(Coord, Coord)], search_area: i64) -> Vec<(i64, IntervalSet<i64>)> {
+    let sensor_ranges = sensor_ranges(input);
+    let columns = IntervalSet::from(0..=search_area);
+    (0..=search_area)
+        .map(|y| (y, columns.subtract(&row_coverage(&sensor_ranges, y))))
+        .filter(|(_, gaps)| !gaps.is_empty())
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mark {
+    Uncovered,
+    Covered,
+    Sensor,
+    Beacon,
+    Distress,
+}
+impl Glyph for Mark {
+    fn symbol(self) -> char {
+        match self {
+            Mark::Uncovered => '.',
+            Mark::Covered => '#',
+            Mark::Sensor => 'S',
+            Mark::Beacon => 'B',
+            Mark::Distress => '!',
+        }
+    }
+    fn color(self) -> [u8; 3] {
+        match self {
+            Mark::Uncovered => [16, 16, 24],
+            Mark::Covered => [40, 70, 110],
+            Mark::Sensor => [240, 240, 240],
+            Mark::Beacon => [90, 200, 90],
+            Mark::Distress => [255, 40, 40],
+        }
+    }
+}
+
+/// Map of the sensors' coverage over the inclusive `window`, with each cell standing for a
+/// `scale`×`scale` block of points.
+///
+/// A block is drawn uncovered if any of its points is, so that gaps stay visible once scaled down.
+/// Sensors, beacons and the distress beacons found in the search area are drawn on top.
+pub fn coverage_map(
+    report: &Report,
+    ((minx, miny), (maxx, maxy)): (Coord, Coord),
+    scale: i64,
+) -> Raster<Mark> {
+    assert!(scale > 0 && minx <= maxx && miny <= maxy);
+    let width = ((maxx - minx) / scale + 1) as usize;
+    let height = ((maxy - miny) / scale + 1) as usize;
+    let mut cells = vec![Mark::Covered; width * height];
+    let cell = |(x, y): Coord| {
+        ((minx..=maxx).contains(&x) && (miny..=maxy).contains(&y))
+            .then(|| ((x - minx) / scale) as usize + ((y - miny) / scale) as usize * width)
+    };
+
+    let sensor_ranges = sensor_ranges(&report.sensors);
+    let columns = IntervalSet::from(minx..=maxx);
+    let fill = |cells: &mut [Mark], y: i64, spans: &IntervalSet<i64>, mark| {
+        let row = ((y - miny) / scale) as usize * width;
+        for (start, end) in spans.iter().map(|span| span.into_inner()) {
+            let (start, end) = (
+                ((start - minx) / scale) as usize,
+                ((end - minx) / scale) as usize,
+            );
+            cells[row + start..=row + end].fill(mark);
+        }
+    };
+
+    // uncovered points of the search area are the distress beacons, which only need to be
+    // looked for in the part of it that is drawn.
+    let search_area = IntervalSet::from(0..=report.params.search_area);
+    let area_columns = columns.intersect(&search_area);
+    let mut distress = Vec::new();
+    for y in miny..=maxy {
+        let gaps = columns.subtract(&row_coverage(&sensor_ranges, y));
+        fill(&mut cells, y, &gaps, Mark::Uncovered);
+        if search_area.contains(y) {
+            distress.push((y, gaps.intersect(&area_columns)));
+        }
+    }
+
+    let beacons = report
+        .sensors
+        .iter()
+        .map(|&(_, beacon)| (beacon, Mark::Beacon));
+    let sensors = report
+        .sensors
+        .iter()
+        .map(|&(sensor, _)| (sensor, Mark::Sensor));
+    for (point, mark) in beacons.chain(sensors) {
+        if let Some(i) = cell(point) {
+            cells[i] = mark;
+        }
+    }
+    for (y, spans) in distress {
+        fill(&mut cells, y, &spans, Mark::Distress);
+    }
+    Raster::new(width, cells)
 }
 
 #[aoc(day15, part1)]
-fn day15_part1(input: &[(Coord, Coord)]) -> usize {
-    check_line(input, 2_000_000)
+fn day15_part1(report: &Report) -> usize {
+    check_line_intervals(&report.sensors, report.params.row)
+}
+#[aoc(day15, part1, scan)]
+fn day15_part1_scan(report: &Report) -> usize {
+    check_line(&report.sensors, report.params.row)
 }
 #[aoc(day15, part2)]
-fn day15_part2(input: &[(Coord, Coord)]) -> Option<i64> {
-    find_distress_beacon(input, 4_000_000)
+fn day15_part2(report: &Report) -> Option<i64> {
+    find_isolated_beacon(&report.sensors, report.params.search_area)
+        .map(|beacon| report.params.tuning_frequency(beacon))
+}
+#[aoc(day15, part2, scan)]
+fn day15_part2_scan(report: &Report) -> Option<i64> {
+    find_distress_beacon(&report.sensors, report.params.search_area)
+        .map(|beacon| report.params.tuning_frequency(beacon))
 }
 
 #[cfg(test)]
 mod test {
+    use itertools::Itertools;
+    use proptest::prelude::*;
+
     const EXAMPLE: &str = r"Sensor at x=2, y=18: closest beacon is at x=-2, y=15
 Sensor at x=9, y=16: closest beacon is at x=10, y=16
 Sensor at x=13, y=2: closest beacon is at x=15, y=3
@@ -110,6 +373,10 @@ Sensor at x=16, y=7: closest beacon is at x=15, y=3
 Sensor at x=14, y=3: closest beacon is at x=15, y=3
 Sensor at x=20, y=1: closest beacon is at x=15, y=3";
 
+    fn sensors() -> Vec<(super::Coord, super::Coord)> {
+        super::day15(EXAMPLE).unwrap().sensors
+    }
+
     #[test]
     fn day15_gen() {
         assert_eq!(
@@ -129,20 +396,171 @@ Sensor at x=20, y=1: closest beacon is at x=15, y=3";
                 ((14, 3), (15, 3)),
                 ((20, 1), (15, 3))
             ][..],
-            &super::day15(EXAMPLE)
+            &super::day15(EXAMPLE).unwrap().sensors
         );
     }
 
     #[test]
     fn day15_part1() {
-        assert_eq!(26, super::check_line(&super::day15(EXAMPLE), 10));
+        assert_eq!(26, super::check_line(&sensors(), 10));
+    }
+
+    #[test]
+    fn day15_params() {
+        let input = format!("row=10, area=20\n{EXAMPLE}");
+        let report = super::day15(&input).unwrap();
+        assert_eq!(
+            super::Params {
+                row: 10,
+                search_area: 20,
+                tuning: 4_000_000
+            },
+            report.params
+        );
+        assert_eq!(26, super::day15_part1(&report));
+        assert_eq!(26, super::day15_part1_scan(&report));
+        assert_eq!(Some(56000011), super::day15_part2(&report));
+        assert_eq!(Some(56000011), super::day15_part2_scan(&report));
+
+        let report = super::day15(&format!("area=20\ntuning=10\n{EXAMPLE}")).unwrap();
+        assert_eq!(Some(151), super::day15_part2(&report));
+
+        assert!(super::day15("depth=3\n").is_err());
+    }
+
+    #[test]
+    fn day15_part1_intervals() {
+        let input = sensors();
+        assert_eq!(
+            super::IntervalSet::from(-2..=24),
+            super::covered_spans(&input, 10)
+        );
+        assert_eq!(26, super::check_line_intervals(&input, 10));
+        for y in -10..30 {
+            assert_eq!(
+                super::check_line(&input, y),
+                super::check_line_intervals(&input, y),
+                "row {y}"
+            );
+        }
     }
 
     #[test]
     fn day15_part2() {
+        assert_eq!(Some((14, 11)), super::find_distress_beacon(&sensors(), 20));
+    }
+
+    #[test]
+    fn day15_part2_geometric() {
+        assert_eq!(Some((14, 11)), super::find_isolated_beacon(&sensors(), 20));
+    }
+
+    #[test]
+    fn day15_uncovered_corner() {
+        // a single sensor only leaves the far corner of the area uncovered.
+        let input = [((0, 0), (5, 5))];
+        assert_eq!(Some((5, 6)), super::find_isolated_beacon(&input, 6));
+        assert_eq!(vec![(5, 6), (6, 5), (6, 6)], uncovered_points(&input, 6));
+    }
+
+    /// Uncovered points of the area, from the spans of [`super::uncovered_rows`].
+    fn uncovered_points(
+        input: &[(super::Coord, super::Coord)],
+        search_area: i64,
+    ) -> Vec<super::Coord> {
+        super::uncovered_rows(input, search_area)
+            .into_iter()
+            .flat_map(|(y, gaps)| gaps.iter().flatten().map(move |x| (x, y)).collect_vec())
+            .sorted_unstable()
+            .collect()
+    }
+
+    /// Uncovered points found by checking every point of the area.
+    fn brute_force(input: &[(super::Coord, super::Coord)], search_area: i64) -> Vec<super::Coord> {
+        let ranges = super::sensor_ranges(input);
+        (0..=search_area)
+            .cartesian_product(0..=search_area)
+            .filter(|&(x, y)| {
+                ranges
+                    .iter()
+                    .all(|&((sx, sy), range)| (sx - x).abs() + (sy - y).abs() > range)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn day15_uncovered_gaps() {
+        // the two diamonds leave a diagonal gap one point wide.
+        let input = [((0, 0), (4, 0)), ((10, 10), (0, 6))];
         assert_eq!(
-            Some(56000011),
-            super::find_distress_beacon(&super::day15(EXAMPLE), 20)
+            vec![(0, 5), (1, 4), (2, 3), (3, 2), (4, 1), (5, 0)],
+            uncovered_points(&input, 10)
         );
+        assert_eq!(Some((0, 5)), super::find_isolated_beacon(&input, 10));
+        // nothing covers the area.
+        assert_eq!(9, uncovered_points(&[((10, 10), (11, 10))], 2).len());
+        assert_eq!(brute_force(&[], 3), uncovered_points(&[], 3));
+        assert_eq!(Some((0, 0)), super::find_isolated_beacon(&[], 3));
+    }
+
+    #[test]
+    fn day15_part2_default_area() {
+        // only the isolated point of the example is looked for, however large the area.
+        let report = super::day15(EXAMPLE).unwrap();
+        assert_eq!(4_000_000, report.params.search_area);
+        assert!(super::day15_part2(&report).is_some());
+    }
+
+    proptest! {
+        #[test]
+        fn day15_uncovered_brute_force(
+            input in prop::collection::vec(((-2i64..14, -2i64..14), (-4i64..16, -4i64..16)), 0..6),
+            search_area in 0i64..12,
+        ) {
+            let expected = brute_force(&input, search_area);
+            prop_assert_eq!(&expected, &uncovered_points(&input, search_area));
+            if let Some(point) = super::find_isolated_beacon(&input, search_area) {
+                prop_assert!(expected.contains(&point));
+            }
+        }
+    }
+
+    #[test]
+    fn day15_coverage_map() {
+        let input = format!("area=20\n{EXAMPLE}");
+        let report = super::day15(&input).unwrap();
+        let map = super::coverage_map(&report, ((0, 0), (20, 20)), 1);
+        assert_eq!((21, 21), (map.width(), map.height()));
+        assert_eq!(Some(super::Mark::Distress), map.get(14, 11));
+        assert_eq!(Some(super::Mark::Sensor), map.get(2, 18));
+        assert_eq!(Some(super::Mark::Beacon), map.get(15, 3));
+        assert!(!map.to_string().contains('.'));
+        println!("{map}");
+
+        // only the drawn part of the default search area is looked at.
+        let report = super::day15(EXAMPLE).unwrap();
+        let map = super::coverage_map(&report, ((0, 0), (20, 20)), 3);
+        assert_eq!(Some(super::Mark::Distress), map.get(4, 3));
+
+        let report =
+            super::day15("area=0\nSensor at x=0, y=0: closest beacon is at x=2, y=0").unwrap();
+        let map = super::coverage_map(&report, ((-3, -3), (3, 3)), 1);
+        let expected = r"
+.......
+...#...
+..###..
+.##S#B.
+..###..
+...#...
+.......
+";
+        assert_eq!(expected, map.to_string());
+
+        let map = super::coverage_map(&report, ((-4, -4), (4, 4)), 3);
+        assert_eq!("\n...\n.SB\n...\n", map.to_string());
+
+        let mut ppm = Vec::new();
+        map.write_ppm(&mut ppm, 2).unwrap();
+        assert!(ppm.starts_with(b"P6\n6 6\n255\n"));
     }
 }
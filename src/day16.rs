@@ -0,0 +1,260 @@
+use std::{cmp::Reverse, collections::HashMap};
+
+use anyhow::{anyhow, bail, Result};
+use itertools::Itertools;
+use yaah::{aoc, aoc_generator};
+
+use crate::pathfinding::distances;
+
+const START: &str = "AA";
+
+/// The valves worth opening and the shortest distances between them.
+///
+/// Valves are indexed by their position in `flows`, the starting valve comes after them.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Network {
+    flows: Vec<u32>,
+    dist: Vec<Vec<u32>>,
+}
+impl Network {
+    fn start(&self) -> usize {
+        self.flows.len()
+    }
+
+    /// Best pressure released in `time` minutes from `pos` with the `opened` valves already open.
+    fn max_pressure(
+        &self,
+        pos: usize,
+        time: u32,
+        opened: u64,
+        memo: &mut HashMap<(usize, u32, u64), u32>,
+    ) -> u32 {
+        if let Some(&pressure) = memo.get(&(pos, time, opened)) {
+            return pressure;
+        }
+        let pressure = (0..self.flows.len())
+            .filter(|&valve| opened & (1 << valve) == 0)
+            .filter_map(|valve| {
+                let time = time.checked_sub(self.dist[pos][valve].saturating_add(1))?;
+                Some(
+                    self.flows[valve] * time
+                        + self.max_pressure(valve, time, opened | (1 << valve), memo),
+                )
+            })
+            .max()
+            .unwrap_or(0);
+        memo.insert((pos, time, opened), pressure);
+        pressure
+    }
+
+    /// Best pressure released in `time` minutes for each set of valves that can be opened in
+    /// that time.
+    fn pressures(&self, time: u32) -> HashMap<u64, u32> {
+        fn visit(
+            network: &Network,
+            best: &mut HashMap<u64, u32>,
+            pos: usize,
+            time: u32,
+            opened: u64,
+            pressure: u32,
+        ) {
+            let entry = best.entry(opened).or_default();
+            *entry = (*entry).max(pressure);
+            for valve in (0..network.flows.len()).filter(|&valve| opened & (1 << valve) == 0) {
+                if let Some(time) = time.checked_sub(network.dist[pos][valve].saturating_add(1)) {
+                    let pressure = pressure + network.flows[valve] * time;
+                    visit(network, best, valve, time, opened | (1 << valve), pressure);
+                }
+            }
+        }
+
+        let mut best = HashMap::new();
+        visit(self, &mut best, self.start(), time, 0, 0);
+        best
+    }
+}
+
+#[aoc_generator(day16)]
+fn day16(input: &str) -> Result<Network> {
+    let re = regex::Regex::new(
+        r"^Valve (\w+) has flow rate=(\d+); tunnels? leads? to valves? (\w+(?:, \w+)*)$",
+    )
+    .unwrap();
+    let valves: Vec<(&str, u32, Vec<&str>)> = input
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(|l| {
+            let caps = re
+                .captures(l)
+                .ok_or_else(|| anyhow!("invalid valve: `{l}`"))?;
+            Ok((
+                caps.get(1).unwrap().as_str(),
+                caps[2].parse()?,
+                caps.get(3).unwrap().as_str().split(", ").collect(),
+            ))
+        })
+        .collect::<Result<_>>()?;
+
+    let index: HashMap<&str, usize> = valves
+        .iter()
+        .enumerate()
+        .map(|(i, &(name, _, _))| (name, i))
+        .collect();
+    let tunnels: Vec<Vec<usize>> = valves
+        .iter()
+        .map(|(name, _, tunnels)| {
+            tunnels
+                .iter()
+                .map(|to| {
+                    index
+                        .get(to)
+                        .copied()
+                        .ok_or_else(|| anyhow!("tunnel from {name} to unknown valve {to}"))
+                })
+                .collect()
+        })
+        .collect::<Result<_>>()?;
+
+    let Some(&start) = index.get(START) else {
+        bail!("missing valve {START}");
+    };
+    let useful = valves
+        .iter()
+        .positions(|&(_, flow, _)| flow > 0)
+        .collect_vec();
+    if useful.len() > 63 {
+        bail!(
+            "too many valves with a non-zero flow rate: {}",
+            useful.len()
+        );
+    }
+
+    let dist = useful
+        .iter()
+        .chain(std::iter::once(&start))
+        .map(|&from| {
+            let reachable = distances(from, |valve| tunnels[valve].iter().copied());
+            useful
+                .iter()
+                .map(|to| reachable.get(to).map_or(u32::MAX, |&d| d as u32))
+                .collect()
+        })
+        .collect();
+    Ok(Network {
+        flows: useful.iter().map(|&valve| valves[valve].1).collect(),
+        dist,
+    })
+}
+
+#[aoc(day16, part1)]
+fn day16_part1(network: &Network) -> u32 {
+    network.max_pressure(network.start(), 30, 0, &mut HashMap::new())
+}
+
+#[aoc(day16, part2)]
+fn day16_part2(network: &Network) -> u32 {
+    let sets = network
+        .pressures(26)
+        .into_iter()
+        .sorted_unstable_by_key(|&(_, pressure)| Reverse(pressure))
+        .collect_vec();
+    // for each set, the best disjoint set ordered after it is the first one found.
+    let mut best = 0;
+    for (i, &(mine, pressure)) in sets.iter().enumerate() {
+        if pressure * 2 <= best {
+            break;
+        }
+        if let Some(&(_, theirs)) = sets[i..]
+            .iter()
+            .take_while(|&&(_, theirs)| pressure + theirs > best)
+            .find(|&&(theirs, _)| mine & theirs == 0)
+        {
+            best = pressure + theirs;
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod test {
+    const EXAMPLE: &str = r"Valve AA has flow rate=0; tunnels lead to valves DD, II, BB
+Valve BB has flow rate=13; tunnels lead to valves CC, AA
+Valve CC has flow rate=2; tunnels lead to valves DD, BB
+Valve DD has flow rate=20; tunnels lead to valves CC, AA, EE
+Valve EE has flow rate=3; tunnels lead to valves FF, DD
+Valve FF has flow rate=0; tunnels lead to valves EE, GG
+Valve GG has flow rate=0; tunnels lead to valves FF, HH
+Valve HH has flow rate=22; tunnel leads to valve GG
+Valve II has flow rate=0; tunnels lead to valves AA, JJ
+Valve JJ has flow rate=21; tunnel leads to valve II";
+
+    #[test]
+    fn day16_gen() {
+        let network = super::day16(EXAMPLE).unwrap();
+        assert_eq!(vec![13, 2, 20, 3, 22, 21], network.flows);
+        // BB, CC, DD, EE, HH, JJ then AA.
+        assert_eq!(
+            vec![
+                vec![0, 1, 2, 3, 6, 3],
+                vec![1, 0, 1, 2, 5, 4],
+                vec![2, 1, 0, 1, 4, 3],
+                vec![3, 2, 1, 0, 3, 4],
+                vec![6, 5, 4, 3, 0, 7],
+                vec![3, 4, 3, 4, 7, 0],
+                vec![1, 2, 1, 2, 5, 2],
+            ],
+            network.dist
+        );
+
+        assert!(super::day16("Valve AA has flow rate=0; tunnel leads to valve BB").is_err());
+        assert!(super::day16("Valve BB has flow rate=1; tunnel leads to valve BB").is_err());
+        assert!(super::day16("Valve AA has flow rate=x; tunnel leads to valve AA").is_err());
+    }
+
+    #[test]
+    fn day16_part1() {
+        assert_eq!(1651, super::day16_part1(&super::day16(EXAMPLE).unwrap()));
+    }
+
+    #[test]
+    fn day16_part2() {
+        assert_eq!(1707, super::day16_part2(&super::day16(EXAMPLE).unwrap()));
+    }
+
+    #[test]
+    fn day16_many_valves() {
+        // 40 valves at the end of tunnels 10 minutes long: each opener has only time for one.
+        let mut input = format!(
+            "Valve AA has flow rate=0; tunnels lead to valves {}\n",
+            (0..40)
+                .map(|k| format!("T{k}x0"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        for k in 0..40 {
+            for i in 0..9 {
+                let prev = if i == 0 {
+                    "AA".to_owned()
+                } else {
+                    format!("T{k}x{}", i - 1)
+                };
+                let next = if i == 8 {
+                    format!("V{k}")
+                } else {
+                    format!("T{k}x{}", i + 1)
+                };
+                input += &format!(
+                    "Valve T{k}x{i} has flow rate=0; tunnels lead to valves {prev}, {next}\n"
+                );
+            }
+            input += &format!(
+                "Valve V{k} has flow rate={}; tunnel leads to valve T{k}x8\n",
+                k + 1
+            );
+        }
+        let network = super::day16(&input).unwrap();
+        assert_eq!(40, network.flows.len());
+        assert_eq!(19 * 40, super::day16_part1(&network));
+        assert_eq!(15 * 40 + 15 * 39, super::day16_part2(&network));
+    }
+}
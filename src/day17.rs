@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Result};
+use yaah::{aoc, aoc_generator};
+
+use crate::render::{Glyph, Raster};
+
+const WIDTH: usize = 7;
+
+/// Rock shapes in their starting column, one byte per row from the bottom up.
+///
+/// Bit 6 is the leftmost column of the chamber and bit 0 the rightmost.
+const ROCKS: [&[u8]; 5] = [
+    &[0b0011110],
+    &[0b0001000, 0b0011100, 0b0001000],
+    &[0b0011100, 0b0000100, 0b0000100],
+    &[0b0010000, 0b0010000, 0b0010000, 0b0010000],
+    &[0b0011000, 0b0011000],
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Jet {
+    Left,
+    Right,
+}
+
+#[aoc_generator(day17)]
+fn day17(input: &str) -> Result<Vec<Jet>> {
+    let jets: Vec<Jet> = input
+        .trim_end()
+        .chars()
+        .map(|c| match c {
+            '<' => Ok(Jet::Left),
+            '>' => Ok(Jet::Right),
+            c => Err(anyhow!("invalid jet `{c}`")),
+        })
+        .collect::<Result<_>>()?;
+    if jets.is_empty() {
+        bail!("empty jet pattern");
+    }
+    Ok(jets)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Block {
+    Air,
+    Rock,
+}
+impl Glyph for Block {
+    fn symbol(self) -> char {
+        match self {
+            Block::Air => '.',
+            Block::Rock => '#',
+        }
+    }
+    fn color(self) -> [u8; 3] {
+        match self {
+            Block::Air => [16, 16, 24],
+            Block::Rock => [128, 128, 128],
+        }
+    }
+}
+
+/// State of the falling rocks that only depends on the jets and rocks to come, and on the shape
+/// of the top of the tower: the rock kind, the jet index and the depth of each column.
+type Fingerprint = (usize, usize, [usize; WIDTH]);
+
+/// Rows looked at from the top of the tower for a [`Fingerprint`]. Deeper columns, or ones never
+/// filled, all count as this deep so that the state still repeats.
+const FINGERPRINT_DEPTH: usize = 64;
+
+pub struct Chamber<'a> {
+    jets: &'a [Jet],
+    rows: Vec<u8>,
+    jet: usize,
+    rocks: usize,
+}
+impl<'a> Chamber<'a> {
+    pub fn new(jets: &'a [Jet]) -> Self {
+        assert!(!jets.is_empty(), "rocks need jets to be pushed by");
+        Self {
+            jets,
+            rows: Vec::new(),
+            jet: 0,
+            rocks: 0,
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    fn collides(&self, rock: &[u8], y: usize) -> bool {
+        rock.iter()
+            .zip(self.rows.iter().skip(y))
+            .any(|(rock, row)| rock & row != 0)
+    }
+
+    pub fn drop_rock(&mut self) {
+        // padded with empty rows, which never collide.
+        let shape = ROCKS[self.rocks % ROCKS.len()];
+        let mut rock = [0u8; 4];
+        rock[..shape.len()].copy_from_slice(shape);
+
+        let mut y = self.height() + 3;
+        loop {
+            let jet = self.jets[self.jet];
+            self.jet = (self.jet + 1) % self.jets.len();
+            let pushed = match jet {
+                Jet::Left if rock.iter().all(|r| r & (1 << (WIDTH - 1)) == 0) => {
+                    Some(rock.map(|r| r << 1))
+                }
+                Jet::Right if rock.iter().all(|r| r & 1 == 0) => Some(rock.map(|r| r >> 1)),
+                _ => None,
+            };
+            if let Some(pushed) = pushed.filter(|pushed| !self.collides(pushed, y)) {
+                rock = pushed;
+            }
+            if y == 0 || self.collides(&rock, y - 1) {
+                break;
+            }
+            y -= 1;
+        }
+
+        for (i, r) in rock[..shape.len()].iter().enumerate() {
+            if y + i == self.rows.len() {
+                self.rows.push(0);
+            }
+            self.rows[y + i] |= r;
+        }
+        self.rocks += 1;
+    }
+
+    fn fingerprint(&self) -> Fingerprint {
+        let depths = std::array::from_fn(|column| {
+            let bit = 1 << (WIDTH - 1 - column);
+            self.rows
+                .iter()
+                .rev()
+                .take(FINGERPRINT_DEPTH)
+                .position(|row| row & bit != 0)
+                .unwrap_or(FINGERPRINT_DEPTH)
+        });
+        (self.rocks % ROCKS.len(), self.jet, depths)
+    }
+
+    /// The top `rows` rows of the tower.
+    pub fn top(&self, rows: usize) -> Raster<Block> {
+        let cells = self
+            .rows
+            .iter()
+            .rev()
+            .take(rows)
+            .flat_map(|row| {
+                (0..WIDTH).rev().map(move |bit| {
+                    if row & (1 << bit) != 0 {
+                        Block::Rock
+                    } else {
+                        Block::Air
+                    }
+                })
+            })
+            .collect();
+        Raster::new(WIDTH, cells)
+    }
+}
+
+/// Height of the tower after `rocks` rocks have fallen.
+///
+/// Once the chamber is back in a state it has already been in, the rocks since then repeat until
+/// the end so the height they add is extrapolated.
+pub fn tower_height(jets: &[Jet], rocks: usize) -> usize {
+    let mut chamber = Chamber::new(jets);
+    let mut seen: HashMap<Fingerprint, (usize, usize)> = HashMap::new();
+    let mut skipped = None;
+    while chamber.rocks < rocks {
+        chamber.drop_rock();
+        if skipped.is_none() {
+            if let Some((start, height)) =
+                seen.insert(chamber.fingerprint(), (chamber.rocks, chamber.height()))
+            {
+                let period = chamber.rocks - start;
+                let cycles = (rocks - chamber.rocks) / period;
+                chamber.rocks += cycles * period;
+                skipped = Some(cycles * (chamber.height() - height));
+            }
+        }
+    }
+    chamber.height() + skipped.unwrap_or(0)
+}
+
+#[aoc(day17, part1)]
+fn day17_part1(jets: &[Jet]) -> usize {
+    tower_height(jets, 2022)
+}
+#[aoc(day17, part1, naive)]
+fn day17_part1_naive(jets: &[Jet]) -> usize {
+    let mut chamber = Chamber::new(jets);
+    (0..2022).for_each(|_| chamber.drop_rock());
+    chamber.height()
+}
+
+#[aoc(day17, part2)]
+fn day17_part2(jets: &[Jet]) -> usize {
+    tower_height(jets, 1_000_000_000_000)
+}
+
+#[cfg(test)]
+mod test {
+    const EXAMPLE: &str = ">>><<><>><<<>><>>><<<>>><<<><<<>><>><<>>\n";
+
+    #[test]
+    fn day17_gen() {
+        let jets = super::day17(EXAMPLE).unwrap();
+        assert_eq!(40, jets.len());
+        assert_eq!(super::Jet::Right, jets[0]);
+        assert_eq!(super::Jet::Left, jets[3]);
+        assert!(super::day17("<>v").is_err());
+        assert!(super::day17("").is_err());
+        assert!(super::day17("\n").is_err());
+    }
+
+    #[test]
+    fn day17_part1() {
+        let jets = super::day17(EXAMPLE).unwrap();
+        assert_eq!(3068, super::day17_part1(&jets));
+        assert_eq!(3068, super::day17_part1_naive(&jets));
+    }
+
+    #[test]
+    fn day17_part2() {
+        let jets = super::day17(EXAMPLE).unwrap();
+        assert_eq!(1514285714288, super::day17_part2(&jets));
+    }
+
+    #[test]
+    fn day17_cycles() {
+        let jets = super::day17(EXAMPLE).unwrap();
+        let mut chamber = super::Chamber::new(&jets);
+        for rocks in 1..500 {
+            chamber.drop_rock();
+            assert_eq!(chamber.height(), super::tower_height(&jets, rocks));
+        }
+    }
+
+    #[test]
+    fn day17_open_column() {
+        // rocks are all pushed to the right, the leftmost column is never filled.
+        let jets = super::day17(">").unwrap();
+        let mut chamber = super::Chamber::new(&jets);
+        for rocks in 1..200 {
+            chamber.drop_rock();
+            assert_eq!(chamber.height(), super::tower_height(&jets, rocks));
+        }
+        while chamber.rocks < 20_000 {
+            chamber.drop_rock();
+        }
+        assert_eq!(chamber.height(), super::tower_height(&jets, 20_000));
+        assert!(super::day17_part2(&jets) > 0);
+    }
+
+    #[test]
+    fn day17_top() {
+        let jets = super::day17(EXAMPLE).unwrap();
+        let mut chamber = super::Chamber::new(&jets);
+        (0..10).for_each(|_| chamber.drop_rock());
+        assert_eq!(17, chamber.height());
+        let expected = r"
+....#..
+....#..
+....##.
+##..##.
+######.
+.###...
+..#....
+.####..
+....##.
+....##.
+....#..
+..#.#..
+..#.#..
+#####..
+..###..
+...#...
+..####.
+";
+        println!("{}", chamber.top(20));
+        assert_eq!(expected, chamber.top(20).to_string());
+    }
+}
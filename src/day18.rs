@@ -0,0 +1,114 @@
+use std::collections::HashSet;
+
+use anyhow::{anyhow, Result};
+use itertools::Itertools;
+use yaah::{aoc, aoc_generator};
+
+use crate::{
+    pathfinding::distances,
+    point::{self, neighbours3, Point3},
+};
+
+pub type Voxels = HashSet<Point3>;
+
+#[aoc_generator(day18)]
+fn day18(input: &str) -> Result<Voxels> {
+    input
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(|l| {
+            l.split(',')
+                .map(|v| v.trim().parse())
+                .collect::<Result<Vec<i32>, _>>()?
+                .into_iter()
+                .collect_tuple()
+                .ok_or_else(|| anyhow!("expected `x,y,z`, got `{l}`"))
+        })
+        .collect()
+}
+
+/// Faces of the cubes not shared with another cube, air pockets included.
+fn surface(voxels: &Voxels) -> usize {
+    voxels
+        .iter()
+        .flat_map(|&voxel| neighbours3(voxel))
+        .filter(|neighbour| !voxels.contains(neighbour))
+        .count()
+}
+
+/// Faces of the cubes reachable from outside, found by flooding the air around the droplet
+/// within its bounding box grown by one in every direction.
+fn exterior_surface(voxels: &Voxels) -> usize {
+    let Some((min, max)) = point::bounds3(voxels.iter().copied()) else {
+        return 0;
+    };
+    let (min, max) = (
+        (min.0 - 1, min.1 - 1, min.2 - 1),
+        (max.0 + 1, max.1 + 1, max.2 + 1),
+    );
+    let inside = |&(x, y, z): &Point3| {
+        (min.0..=max.0).contains(&x) && (min.1..=max.1).contains(&y) && (min.2..=max.2).contains(&z)
+    };
+
+    let outside = distances(min, |voxel| {
+        neighbours3(voxel)
+            .into_iter()
+            .filter(|neighbour| inside(neighbour) && !voxels.contains(neighbour))
+    });
+    outside
+        .keys()
+        .flat_map(|&air| neighbours3(air))
+        .filter(|neighbour| voxels.contains(neighbour))
+        .count()
+}
+
+#[aoc(day18, part1)]
+fn day18_part1(voxels: &Voxels) -> usize {
+    surface(voxels)
+}
+
+#[aoc(day18, part2)]
+fn day18_part2(voxels: &Voxels) -> usize {
+    exterior_surface(voxels)
+}
+
+#[cfg(test)]
+mod test {
+    const EXAMPLE: &str = r"2,2,2
+1,2,2
+3,2,2
+2,1,2
+2,3,2
+2,2,1
+2,2,3
+2,2,4
+2,2,6
+1,2,5
+3,2,5
+2,1,5
+2,3,5";
+
+    #[test]
+    fn day18_gen() {
+        let voxels = super::day18(EXAMPLE).unwrap();
+        assert_eq!(13, voxels.len());
+        assert!(voxels.contains(&(1, 2, 5)));
+        assert!(super::day18("1,2").is_err());
+        assert!(super::day18("1,2,a").is_err());
+    }
+
+    #[test]
+    fn day18_part1() {
+        assert_eq!(
+            10,
+            super::day18_part1(&super::day18("1,1,1\n2,1,1").unwrap())
+        );
+        assert_eq!(64, super::day18_part1(&super::day18(EXAMPLE).unwrap()));
+    }
+
+    #[test]
+    fn day18_part2() {
+        assert_eq!(58, super::day18_part2(&super::day18(EXAMPLE).unwrap()));
+        assert_eq!(0, super::day18_part2(&super::day18("").unwrap()));
+    }
+}
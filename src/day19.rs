@@ -0,0 +1,175 @@
+use anyhow::Result;
+use rayon::prelude::*;
+use yaah::{aoc, aoc_generator};
+
+const ORE: usize = 0;
+const GEODE: usize = 3;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Blueprint {
+    id: u32,
+    /// Ore, clay and obsidian needed for each kind of robot.
+    costs: [[u32; 3]; 4],
+}
+impl Blueprint {
+    /// Most of each resource that can be spent in a minute: having more robots collecting it
+    /// than that is useless.
+    fn max_needed(&self) -> [u32; 3] {
+        std::array::from_fn(|resource| self.costs.iter().map(|cost| cost[resource]).max().unwrap())
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct State {
+    time: u32,
+    robots: [u32; 3],
+    resources: [u32; 3],
+    /// Geodes opened by the end, counted as soon as their robot is built.
+    geodes: u32,
+}
+
+#[aoc_generator(day19)]
+fn day19(input: &str) -> Result<Vec<Blueprint>> {
+    let re = regex::Regex::new(
+        r"Blueprint (\d+):\s+Each ore robot costs (\d+) ore\.\s+Each clay robot costs (\d+) ore\.\s+Each obsidian robot costs (\d+) ore and (\d+) clay\.\s+Each geode robot costs (\d+) ore and (\d+) obsidian\.",
+    )
+    .unwrap();
+    re.captures_iter(input)
+        .map(|caps| {
+            let v = |i: usize| caps[i].parse::<u32>();
+            Ok(Blueprint {
+                id: v(1)?,
+                costs: [
+                    [v(2)?, 0, 0],
+                    [v(3)?, 0, 0],
+                    [v(4)?, v(5)?, 0],
+                    [v(6)?, 0, v(7)?],
+                ],
+            })
+        })
+        .collect()
+}
+
+/// Depth first search over the next robot to build, skipping the minutes spent waiting for it.
+fn search(blueprint: &Blueprint, max_needed: &[u32; 3], state: State, best: &mut u32) {
+    *best = (*best).max(state.geodes);
+    // even building a geode robot every remaining minute would not beat the best.
+    let t = state.time;
+    if state.geodes + t * t.saturating_sub(1) / 2 <= *best {
+        return;
+    }
+
+    for robot in (ORE..=GEODE).rev() {
+        if robot != GEODE && state.robots[robot] >= max_needed[robot] {
+            continue;
+        }
+        let cost = &blueprint.costs[robot];
+        let Some(wait) = (0..3).try_fold(0, |wait, resource| {
+            let missing = cost[resource].saturating_sub(state.resources[resource]);
+            if missing == 0 {
+                Some(wait)
+            } else if state.robots[resource] == 0 {
+                None
+            } else {
+                Some(wait.max(missing.div_ceil(state.robots[resource])))
+            }
+        }) else {
+            continue;
+        };
+        let Some(time) = t.checked_sub(wait + 1).filter(|&time| time > 0) else {
+            continue;
+        };
+
+        let mut next = State {
+            time,
+            resources: std::array::from_fn(|resource| {
+                state.resources[resource] + state.robots[resource] * (wait + 1) - cost[resource]
+            }),
+            ..state
+        };
+        if robot == GEODE {
+            next.geodes += time;
+        } else {
+            next.robots[robot] += 1;
+        }
+        search(blueprint, max_needed, next, best);
+    }
+}
+
+pub fn max_geodes(blueprint: &Blueprint, time: u32) -> u32 {
+    let mut best = 0;
+    let state = State {
+        time,
+        robots: [1, 0, 0],
+        resources: [0; 3],
+        geodes: 0,
+    };
+    search(blueprint, &blueprint.max_needed(), state, &mut best);
+    best
+}
+
+#[aoc(day19, part1)]
+fn day19_part1(blueprints: &[Blueprint]) -> u32 {
+    blueprints
+        .par_iter()
+        .map(|blueprint| blueprint.id * max_geodes(blueprint, 24))
+        .sum()
+}
+
+#[aoc(day19, part2)]
+fn day19_part2(blueprints: &[Blueprint]) -> u32 {
+    blueprints[..blueprints.len().min(3)]
+        .par_iter()
+        .map(|blueprint| max_geodes(blueprint, 32))
+        .product()
+}
+
+#[cfg(test)]
+mod test {
+    const EXAMPLE: &str = r"Blueprint 1:
+  Each ore robot costs 4 ore.
+  Each clay robot costs 2 ore.
+  Each obsidian robot costs 3 ore and 14 clay.
+  Each geode robot costs 2 ore and 7 obsidian.
+
+Blueprint 2:
+  Each ore robot costs 2 ore.
+  Each clay robot costs 3 ore.
+  Each obsidian robot costs 3 ore and 8 clay.
+  Each geode robot costs 3 ore and 12 obsidian.";
+
+    #[test]
+    fn day19_gen() {
+        let blueprints = super::day19(EXAMPLE).unwrap();
+        assert_eq!(
+            vec![
+                super::Blueprint {
+                    id: 1,
+                    costs: [[4, 0, 0], [2, 0, 0], [3, 14, 0], [2, 0, 7]]
+                },
+                super::Blueprint {
+                    id: 2,
+                    costs: [[2, 0, 0], [3, 0, 0], [3, 8, 0], [3, 0, 12]]
+                },
+            ],
+            blueprints
+        );
+        // one blueprint per line as in the real input.
+        let blueprints = super::day19(&EXAMPLE.replace("\n ", "").replace("\n\n", "\n")).unwrap();
+        assert_eq!(2, blueprints.len());
+    }
+
+    #[test]
+    fn day19_part1() {
+        let blueprints = super::day19(EXAMPLE).unwrap();
+        assert_eq!(9, super::max_geodes(&blueprints[0], 24));
+        assert_eq!(12, super::max_geodes(&blueprints[1], 24));
+        assert_eq!(33, super::day19_part1(&blueprints));
+    }
+
+    #[test]
+    fn day19_part2() {
+        let blueprints = super::day19(EXAMPLE).unwrap();
+        assert_eq!(56 * 62, super::day19_part2(&blueprints));
+    }
+}
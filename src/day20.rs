@@ -0,0 +1,236 @@
+use anyhow::Result;
+use yaah::{aoc, aoc_generator};
+
+const DECRYPTION_KEY: i64 = 811_589_153;
+
+#[aoc_generator(day20)]
+fn day20(input: &str) -> Result<Vec<i64>> {
+    input
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(|l| Ok(l.trim().parse()?))
+        .collect()
+}
+
+/// Circular arrangement of the numbers, identified by their index in the file so that duplicates
+/// stay distinct.
+pub trait Sequence: FromIterator<usize> {
+    fn position(&self, id: usize) -> usize;
+    fn remove(&mut self, pos: usize) -> usize;
+    fn insert(&mut self, pos: usize, id: usize);
+    fn order(&self) -> Vec<usize>;
+}
+
+impl Sequence for Vec<usize> {
+    fn position(&self, id: usize) -> usize {
+        self.iter().position(|&v| v == id).unwrap()
+    }
+    fn remove(&mut self, pos: usize) -> usize {
+        Vec::remove(self, pos)
+    }
+    fn insert(&mut self, pos: usize, id: usize) {
+        Vec::insert(self, pos, id)
+    }
+    fn order(&self) -> Vec<usize> {
+        self.clone()
+    }
+}
+
+/// Sequence split in blocks of about √n ids, making each operation O(√n).
+///
+/// Blocks are rebuilt once one grows to twice its initial size.
+pub struct Blocks {
+    size: usize,
+    blocks: Vec<Vec<usize>>,
+    block_of: Vec<usize>,
+}
+impl Blocks {
+    fn rebuild(&mut self, order: Vec<usize>) {
+        self.blocks = order.chunks(self.size).map(<[usize]>::to_vec).collect();
+        if self.blocks.is_empty() {
+            // An empty sequence still needs a block to insert into.
+            self.blocks.push(Vec::new());
+        }
+        for (b, block) in self.blocks.iter().enumerate() {
+            block.iter().for_each(|&id| self.block_of[id] = b);
+        }
+    }
+
+    /// Block holding `pos` and the position within it.
+    fn locate(&self, mut pos: usize) -> (usize, usize) {
+        for (b, block) in self.blocks.iter().enumerate() {
+            if pos < block.len() {
+                return (b, pos);
+            }
+            pos -= block.len();
+        }
+        let last = self.blocks.len() - 1;
+        (last, pos + self.blocks[last].len())
+    }
+}
+impl FromIterator<usize> for Blocks {
+    fn from_iter<T: IntoIterator<Item = usize>>(iter: T) -> Self {
+        let order: Vec<usize> = iter.into_iter().collect();
+        let mut blocks = Self {
+            size: (order.len() as f64).sqrt().ceil().max(1.) as usize,
+            blocks: Vec::new(),
+            block_of: vec![0; order.len()],
+        };
+        blocks.rebuild(order);
+        blocks
+    }
+}
+impl Sequence for Blocks {
+    fn position(&self, id: usize) -> usize {
+        let b = self.block_of[id];
+        let before: usize = self.blocks[..b].iter().map(Vec::len).sum();
+        before + self.blocks[b].iter().position(|&v| v == id).unwrap()
+    }
+    fn remove(&mut self, pos: usize) -> usize {
+        let (b, pos) = self.locate(pos);
+        self.blocks[b].remove(pos)
+    }
+    fn insert(&mut self, pos: usize, id: usize) {
+        let (b, pos) = self.locate(pos);
+        self.blocks[b].insert(pos, id);
+        if id >= self.block_of.len() {
+            self.block_of.resize(id + 1, 0);
+        }
+        self.block_of[id] = b;
+        if self.blocks[b].len() > 2 * self.size {
+            self.rebuild(self.order());
+        }
+    }
+    fn order(&self) -> Vec<usize> {
+        self.blocks.concat()
+    }
+}
+
+/// The numbers multiplied by `key` after mixing them `rounds` times.
+pub fn mix<S: Sequence>(numbers: &[i64], key: i64, rounds: usize) -> Vec<i64> {
+    let numbers: Vec<i64> = numbers.iter().map(|v| v * key).collect();
+    if numbers.len() < 2 {
+        return numbers;
+    }
+    let mut seq: S = (0..numbers.len()).collect();
+    let modulo = numbers.len() as i64 - 1;
+    for _ in 0..rounds {
+        for (id, value) in numbers.iter().enumerate() {
+            let pos = seq.position(id);
+            seq.remove(pos);
+            seq.insert((pos as i64 + value).rem_euclid(modulo) as usize, id);
+        }
+    }
+    seq.order().into_iter().map(|id| numbers[id]).collect()
+}
+
+fn grove_coordinates(mixed: &[i64]) -> Option<i64> {
+    let zero = mixed.iter().position(|&v| v == 0)?;
+    Some(
+        [1000, 2000, 3000]
+            .into_iter()
+            .map(|offset| mixed[(zero + offset) % mixed.len()])
+            .sum(),
+    )
+}
+
+#[aoc(day20, part1)]
+fn day20_part1(numbers: &[i64]) -> Option<i64> {
+    grove_coordinates(&mix::<Blocks>(numbers, 1, 1))
+}
+#[aoc(day20, part1, naive)]
+fn day20_part1_naive(numbers: &[i64]) -> Option<i64> {
+    grove_coordinates(&mix::<Vec<usize>>(numbers, 1, 1))
+}
+
+#[aoc(day20, part2)]
+fn day20_part2(numbers: &[i64]) -> Option<i64> {
+    grove_coordinates(&mix::<Blocks>(numbers, DECRYPTION_KEY, 10))
+}
+#[aoc(day20, part2, naive)]
+fn day20_part2_naive(numbers: &[i64]) -> Option<i64> {
+    grove_coordinates(&mix::<Vec<usize>>(numbers, DECRYPTION_KEY, 10))
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use super::{Blocks, Sequence};
+
+    const EXAMPLE: &str = r"1
+2
+-3
+3
+-2
+0
+4";
+
+    /// Rotates `mixed` so that it starts at the first value, as the puzzle's examples do.
+    fn from_first(mixed: &[i64], first: i64) -> Vec<i64> {
+        let start = mixed.iter().position(|&v| v == first).unwrap();
+        mixed[start..]
+            .iter()
+            .chain(&mixed[..start])
+            .copied()
+            .collect()
+    }
+
+    #[test]
+    fn day20_mix() {
+        let numbers = super::day20(EXAMPLE).unwrap();
+        let mixed = super::mix::<Blocks>(&numbers, 1, 1);
+        assert_eq!(vec![1, 2, -3, 4, 0, 3, -2], from_first(&mixed, 1));
+        let mixed = super::mix::<Vec<usize>>(&numbers, 1, 1);
+        assert_eq!(vec![1, 2, -3, 4, 0, 3, -2], from_first(&mixed, 1));
+    }
+
+    #[test]
+    fn day20_part1() {
+        let numbers = super::day20(EXAMPLE).unwrap();
+        assert_eq!(Some(3), super::day20_part1(&numbers));
+        assert_eq!(Some(3), super::day20_part1_naive(&numbers));
+    }
+
+    #[test]
+    fn day20_part2() {
+        let numbers = super::day20(EXAMPLE).unwrap();
+        assert_eq!(Some(1623178306), super::day20_part2(&numbers));
+        assert_eq!(Some(1623178306), super::day20_part2_naive(&numbers));
+    }
+
+    #[test]
+    fn day20_blocks() {
+        let mut blocks: Blocks = (0..10).collect();
+        assert_eq!(4, blocks.size);
+        for _ in 0..20 {
+            let id = blocks.remove(9);
+            blocks.insert(0, id);
+        }
+        assert_eq!(vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9], blocks.order());
+        assert!((0..10).all(|id| blocks.order()[blocks.position(id)] == id));
+    }
+
+    #[test]
+    fn day20_blocks_empty() {
+        let mut blocks: Blocks = std::iter::empty().collect();
+        assert!(blocks.order().is_empty());
+        blocks.insert(0, 1);
+        blocks.insert(0, 0);
+        assert_eq!(vec![0, 1], blocks.order());
+        assert_eq!(1, blocks.position(1));
+        assert_eq!(0, blocks.remove(0));
+        assert_eq!(1, blocks.remove(0));
+        assert!(blocks.order().is_empty());
+    }
+
+    proptest! {
+        #[test]
+        fn day20_blocks_match_naive(numbers in prop::collection::vec(-20i64..20, 0..40)) {
+            prop_assert_eq!(
+                super::mix::<Vec<usize>>(&numbers, 3, 2),
+                super::mix::<Blocks>(&numbers, 3, 2)
+            );
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Result};
+use num_rational::Ratio;
+use yaah::{aoc, aoc_generator};
+
+use crate::expr::{Expr, Operand};
+
+type Rational = Ratio<i128>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Job {
+    Yell(i128),
+    /// Operation on the numbers yelled by other monkeys, referred to by their index.
+    Compute(Expr<usize, i128>),
+}
+
+#[derive(Debug)]
+pub struct Monkeys {
+    jobs: Vec<Job>,
+    root: usize,
+    humn: usize,
+}
+impl Monkeys {
+    /// Number yelled by `monkey`, each monkey being evaluated once.
+    fn eval(&self, monkey: usize, memo: &mut [Option<Rational>]) -> Result<Rational> {
+        if let Some(value) = memo[monkey] {
+            return Ok(value);
+        }
+        let value = match &self.jobs[monkey] {
+            Job::Yell(n) => Rational::from(*n),
+            Job::Compute(expr) => expr.try_eval(|&operand| self.eval(operand, memo))?,
+        };
+        memo[monkey] = Some(value);
+        Ok(value)
+    }
+
+    /// Whether the number yelled by `monkey` depends on the one yelled by `target`.
+    fn depends_on(&self, monkey: usize, target: usize, memo: &mut [Option<bool>]) -> bool {
+        if let Some(depends) = memo[monkey] {
+            return depends;
+        }
+        let depends = monkey == target || match &self.jobs[monkey] {
+            Job::Yell(_) => false,
+            Job::Compute(expr) => [expr.lhs, expr.rhs].into_iter().any(
+                |operand| matches!(operand, Operand::Var(m) if self.depends_on(m, target, memo)),
+            ),
+        };
+        memo[monkey] = Some(depends);
+        depends
+    }
+
+    /// Number `humn` must yell for both operands of `root` to be equal.
+    ///
+    /// Starting from `root`, the operation of each monkey on the way to `humn` is inverted to find
+    /// what its operand depending on `humn` must be.
+    fn solve(&self) -> Result<Rational> {
+        let mut depends = vec![None; self.jobs.len()];
+        let mut memo = vec![None; self.jobs.len()];
+        let mut monkey = self.root;
+        // `None` while at the root, whose operands only need to be equal.
+        let mut target: Option<Rational> = None;
+        while monkey != self.humn {
+            let Job::Compute(expr) = &self.jobs[monkey] else {
+                bail!("`humn` is not an operand of monkey {monkey}");
+            };
+            let mut unknown = |operand| matches!(operand, Operand::Var(m) if self.depends_on(m, self.humn, &mut depends));
+            let (next, known, on_lhs) = match (unknown(expr.lhs), unknown(expr.rhs)) {
+                (true, false) => (expr.lhs, expr.rhs, true),
+                (false, true) => (expr.rhs, expr.lhs, false),
+                (true, true) => bail!("both operands of monkey {monkey} depend on `humn`"),
+                (false, false) => bail!("monkey {monkey} does not depend on `humn`"),
+            };
+            let known = match known {
+                Operand::Var(m) => self.eval(m, &mut memo)?,
+                Operand::Num(n) => Rational::from(n),
+            };
+            target = Some(
+                match target {
+                    None => Some(known),
+                    Some(result) if on_lhs => expr.op.solve_lhs(known, result),
+                    Some(result) => expr.op.solve_rhs(known, result),
+                }
+                .ok_or_else(|| anyhow!("monkey {monkey} yells the same for any number"))?,
+            );
+            monkey = match next {
+                Operand::Var(m) => m,
+                Operand::Num(_) => unreachable!(),
+            };
+        }
+        target.ok_or_else(|| anyhow!("`root` cannot be `humn`"))
+    }
+}
+
+fn to_integer(value: Rational) -> Result<i128> {
+    if value.is_integer() {
+        Ok(value.to_integer())
+    } else {
+        Err(anyhow!("{value} is not an integer"))
+    }
+}
+
+#[aoc_generator(day21)]
+fn day21(input: &str) -> Result<Monkeys> {
+    let lines: Vec<(&str, &str)> = input
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(|l| {
+            l.split_once(": ")
+                .ok_or_else(|| anyhow!("expected `name: job`, got `{l}`"))
+        })
+        .collect::<Result<_>>()?;
+    let index: HashMap<&str, usize> = lines
+        .iter()
+        .enumerate()
+        .map(|(i, &(name, _))| (name, i))
+        .collect();
+    let jobs = lines
+        .iter()
+        .map(|&(_, job)| {
+            Ok(match job.trim().parse() {
+                Ok(n) => Job::Yell(n),
+                Err(_) => Job::Compute(Expr::parse(job, |name| index.get(name).copied())?),
+            })
+        })
+        .collect::<Result<_>>()?;
+    let find = |name| {
+        index
+            .get(name)
+            .copied()
+            .ok_or_else(|| anyhow!("missing monkey `{name}`"))
+    };
+    Ok(Monkeys {
+        jobs,
+        root: find("root")?,
+        humn: find("humn")?,
+    })
+}
+
+#[aoc(day21, part1)]
+fn day21_part1(monkeys: &Monkeys) -> Result<i128> {
+    to_integer(monkeys.eval(monkeys.root, &mut vec![None; monkeys.jobs.len()])?)
+}
+
+#[aoc(day21, part2)]
+fn day21_part2(monkeys: &Monkeys) -> Result<i128> {
+    to_integer(monkeys.solve()?)
+}
+
+#[cfg(test)]
+mod test {
+    const EXAMPLE: &str = r"root: pppw + sjmn
+dbpl: 5
+cczh: sllz + lgvd
+zczc: 2
+ptdq: humn - dvpt
+dvpt: 3
+lfqf: 4
+humn: 5
+ljgn: 2
+sjmn: drzm * dbpl
+sllz: 4
+pppw: cczh / lfqf
+lgvd: ljgn * ptdq
+drzm: hmdt - zczc
+hmdt: 32";
+
+    #[test]
+    fn day21_part1() {
+        assert_eq!(
+            152,
+            super::day21_part1(&super::day21(EXAMPLE).unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn day21_part2() {
+        assert_eq!(
+            301,
+            super::day21_part2(&super::day21(EXAMPLE).unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn day21_errors() {
+        assert!(super::day21("root: a + humn\nhumn: 5").is_err());
+        assert!(super::day21("root: 1 % humn\nhumn: 5").is_err());
+
+        let monkeys = super::day21("root: a / b\na: 1\nb: 0\nhumn: 5").unwrap();
+        assert!(super::day21_part1(&monkeys).is_err());
+        assert!(super::day21_part2(&monkeys).is_err());
+
+        let monkeys = super::day21("root: a / b\na: 1\nb: 2\nhumn: 5").unwrap();
+        assert!(super::day21_part1(&monkeys).is_err());
+
+        let monkeys = super::day21("root: humn + humn\nhumn: 5").unwrap();
+        assert!(super::day21_part2(&monkeys).is_err());
+
+        // humn * 0 == 3 has no solution.
+        let monkeys = super::day21("root: a + 3\na: humn * 0\nhumn: 5").unwrap();
+        assert!(super::day21_part2(&monkeys).is_err());
+
+        // fractions are kept exact: humn / 3 * 3 == 4 needs humn == 4.
+        let monkeys = super::day21("root: a + 4\na: b * 3\nb: humn / 3\nhumn: 5").unwrap();
+        assert_eq!(4, super::day21_part2(&monkeys).unwrap());
+    }
+}
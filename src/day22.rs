@@ -0,0 +1,379 @@
+use std::collections::VecDeque;
+
+use anyhow::{anyhow, bail, Result};
+use yaah::{aoc, aoc_generator};
+
+use crate::{
+    grid::Grid,
+    point::{Point, Point3},
+};
+
+/// Steps for each facing: right, down, left and up.
+const DIRS: [Point; 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tile {
+    Void,
+    Open,
+    Wall,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Move {
+    Forward(usize),
+    Left,
+    Right,
+}
+
+pub type Board = Grid<Tile>;
+
+#[aoc_generator(day22)]
+fn day22(input: &str) -> Result<(Board, Vec<Move>)> {
+    let (board, path) = input
+        .split_once("\n\n")
+        .ok_or_else(|| anyhow!("missing the path after the board"))?;
+    let rows = board
+        .lines()
+        .map(|l| {
+            l.chars()
+                .map(|c| match c {
+                    ' ' => Ok(Tile::Void),
+                    '.' => Ok(Tile::Open),
+                    '#' => Ok(Tile::Wall),
+                    c => Err(anyhow!("invalid tile `{c}`")),
+                })
+                .collect::<Result<Vec<_>>>()
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut moves = Vec::new();
+    let mut steps = None;
+    for c in path.trim().chars() {
+        if let Some(digit) = c.to_digit(10) {
+            steps = Some(steps.unwrap_or(0) * 10 + digit as usize);
+            continue;
+        }
+        moves.extend(steps.take().map(Move::Forward));
+        moves.push(match c {
+            'L' => Move::Left,
+            'R' => Move::Right,
+            c => bail!("invalid move `{c}`"),
+        });
+    }
+    moves.extend(steps.map(Move::Forward));
+    Ok((Grid::from_rows(rows, Tile::Void), moves))
+}
+
+/// Where walking off the board leads.
+pub trait Wrap {
+    /// Position and facing after stepping off the board from `pos` facing `dir`.
+    fn wrap(&self, board: &Board, pos: Point, dir: usize) -> (Point, usize);
+}
+
+/// Part 1: reappear on the other side of the row or column.
+pub struct Flat;
+impl Wrap for Flat {
+    fn wrap(&self, board: &Board, mut pos: Point, dir: usize) -> (Point, usize) {
+        let (dx, dy) = DIRS[dir];
+        while board
+            .get((pos.0 - dx, pos.1 - dy))
+            .is_some_and(|&tile| tile != Tile::Void)
+        {
+            pos = (pos.0 - dx, pos.1 - dy);
+        }
+        (pos, dir)
+    }
+}
+
+fn add((ax, ay, az): Point3, (bx, by, bz): Point3) -> Point3 {
+    (ax + bx, ay + by, az + bz)
+}
+fn neg((x, y, z): Point3) -> Point3 {
+    (-x, -y, -z)
+}
+fn scale((x, y, z): Point3, k: i32) -> Point3 {
+    (x * k, y * k, z * k)
+}
+fn dot((ax, ay, az): Point3, (bx, by, bz): Point3) -> i32 {
+    ax * bx + ay * by + az * bz
+}
+
+/// A face of the cube: its top left corner on the board and how it is oriented once folded.
+#[derive(Clone, Copy, Debug)]
+struct Face {
+    origin: Point,
+    /// Outward normal.
+    normal: Point3,
+    /// Directions of the board's `x` and `y` axes on the face.
+    right: Point3,
+    down: Point3,
+}
+impl Face {
+    /// Direction of the board's facing `dir` on the face.
+    fn direction(&self, dir: usize) -> Point3 {
+        [self.right, self.down, neg(self.right), neg(self.down)][dir]
+    }
+
+    /// Face reached by rolling the cube over the edge in the board's facing `dir`.
+    fn roll(&self, dir: usize, origin: Point) -> Face {
+        let (normal, right, down) = match dir {
+            0 => (self.right, neg(self.normal), self.down),
+            1 => (self.down, self.right, neg(self.normal)),
+            2 => (neg(self.right), self.normal, self.down),
+            _ => (neg(self.down), self.right, self.normal),
+        };
+        Face {
+            origin,
+            normal,
+            right,
+            down,
+        }
+    }
+}
+
+/// Part 2: the board is the net of a cube, folded to find which edges meet.
+///
+/// Faces are oriented by rolling a cube over the net from the first face. Cells are then placed
+/// in 3D, centered on the origin with doubled coordinates so that they are integers: the faces lie
+/// at `±size` and the cells' centers at odd offsets from each face's center.
+pub struct Cube {
+    size: i32,
+    faces: Vec<Face>,
+}
+impl Cube {
+    pub fn fold(board: &Board) -> Result<Self> {
+        let tiles = board
+            .iter()
+            .filter(|&(_, &tile)| tile != Tile::Void)
+            .count();
+        let size = ((tiles / 6) as f64).sqrt() as i32;
+        if size == 0 || (size * size * 6) as usize != tiles {
+            bail!("{tiles} tiles cannot make the faces of a cube");
+        }
+        let is_face = |(x, y): Point| {
+            board
+                .get((x * size, y * size))
+                .is_some_and(|&tile| tile != Tile::Void)
+        };
+
+        let first = (0..board.width() as i32 / size)
+            .map(|x| (x, 0))
+            .find(|&p| is_face(p))
+            .ok_or_else(|| anyhow!("first row is empty"))?;
+        let mut faces = vec![Face {
+            origin: first,
+            normal: (0, 0, 1),
+            right: (1, 0, 0),
+            down: (0, 1, 0),
+        }];
+        let mut queue = VecDeque::from([faces[0]]);
+        while let Some(face) = queue.pop_front() {
+            for (dir, (dx, dy)) in DIRS.into_iter().enumerate() {
+                let origin = (face.origin.0 + dx, face.origin.1 + dy);
+                if is_face(origin) && faces.iter().all(|f| f.origin != origin) {
+                    faces.push(face.roll(dir, origin));
+                    queue.push_back(*faces.last().unwrap());
+                }
+            }
+        }
+
+        if faces.len() != 6
+            || (1..6).any(|i| faces[..i].iter().any(|f| f.normal == faces[i].normal))
+        {
+            bail!("the board is not the net of a cube");
+        }
+        faces
+            .iter_mut()
+            .for_each(|face| face.origin = (face.origin.0 * size, face.origin.1 * size));
+        Ok(Self { size, faces })
+    }
+
+    fn face_at(&self, (x, y): Point) -> &Face {
+        self.faces
+            .iter()
+            .find(|f| {
+                (0..self.size).contains(&(x - f.origin.0))
+                    && (0..self.size).contains(&(y - f.origin.1))
+            })
+            .expect("position is on a face")
+    }
+
+    fn face_towards(&self, normal: Point3) -> &Face {
+        self.faces.iter().find(|f| f.normal == normal).unwrap()
+    }
+}
+impl Wrap for Cube {
+    fn wrap(&self, _: &Board, (x, y): Point, dir: usize) -> (Point, usize) {
+        let n = self.size;
+        let from = self.face_at((x, y));
+        let (i, j) = (x - from.origin.0, y - from.origin.1);
+        let pos = add(
+            scale(from.normal, n),
+            add(
+                scale(from.right, 2 * i + 1 - n),
+                scale(from.down, 2 * j + 1 - n),
+            ),
+        );
+
+        // over the edge, the cell next to `pos` is one step along the new face's normal and one
+        // step back from the old one.
+        let to = self.face_towards(from.direction(dir));
+        let pos = add(pos, add(to.normal, neg(from.normal)));
+        let (i, j) = (
+            (dot(pos, to.right) + n - 1) / 2,
+            (dot(pos, to.down) + n - 1) / 2,
+        );
+        let dir = (0..4)
+            .find(|&dir| to.direction(dir) == neg(from.normal))
+            .unwrap();
+        ((to.origin.0 + i, to.origin.1 + j), dir)
+    }
+}
+
+fn walk(board: &Board, path: &[Move], wrap: &impl Wrap) -> Option<(Point, usize)> {
+    let mut pos = (0..board.width() as i32)
+        .map(|x| (x, 0))
+        .find(|&p| board[p] == Tile::Open)?;
+    let mut dir = 0;
+    for &m in path {
+        match m {
+            Move::Left => dir = (dir + 3) % 4,
+            Move::Right => dir = (dir + 1) % 4,
+            Move::Forward(steps) => {
+                for _ in 0..steps {
+                    let (dx, dy) = DIRS[dir];
+                    let next = (pos.0 + dx, pos.1 + dy);
+                    let (next, next_dir) = match board.get(next) {
+                        Some(Tile::Open | Tile::Wall) => (next, dir),
+                        _ => wrap.wrap(board, pos, dir),
+                    };
+                    if board[next] == Tile::Wall {
+                        break;
+                    }
+                    (pos, dir) = (next, next_dir);
+                }
+            }
+        }
+    }
+    Some((pos, dir))
+}
+
+fn password(((x, y), dir): (Point, usize)) -> usize {
+    1000 * (y as usize + 1) + 4 * (x as usize + 1) + dir
+}
+
+#[aoc(day22, part1)]
+fn day22_part1((board, path): &(Board, Vec<Move>)) -> Option<usize> {
+    walk(board, path, &Flat).map(password)
+}
+
+#[aoc(day22, part2)]
+fn day22_part2((board, path): &(Board, Vec<Move>)) -> Result<usize> {
+    let cube = Cube::fold(board)?;
+    walk(board, path, &cube)
+        .map(password)
+        .ok_or_else(|| anyhow!("no open tile on the first row"))
+}
+
+#[cfg(test)]
+mod test {
+    use itertools::Itertools;
+
+    use super::{Board, Cube, Move, Tile};
+
+    const EXAMPLE: &str = r"        ...#
+        .#..
+        #...
+        ....
+...#.......#
+........#...
+..#....#....
+..........#.
+        ...#....
+        .....#..
+        .#......
+        ......#.
+
+10R5L5R10L4R5L5
+";
+
+    /// Wall-free board of faces of `size` tiles laid out as in `net`.
+    fn net(net: &str, size: usize) -> Board {
+        let rows = net.lines().flat_map(|l| {
+            let row = l
+                .chars()
+                .flat_map(|c| {
+                    let tile = if c == '#' { Tile::Open } else { Tile::Void };
+                    std::iter::repeat_n(tile, size)
+                })
+                .collect_vec();
+            std::iter::repeat_n(row, size)
+        });
+        Board::from_rows(rows, Tile::Void)
+    }
+
+    #[test]
+    fn day22_gen() {
+        let (board, path) = super::day22(EXAMPLE).unwrap();
+        assert_eq!((16, 12), (board.width(), board.height()));
+        assert_eq!(Tile::Void, board[(0, 0)]);
+        assert_eq!(Tile::Wall, board[(11, 0)]);
+        assert_eq!(Tile::Void, board[(15, 0)]);
+        assert_eq!(
+            &[Move::Forward(10), Move::Right, Move::Forward(5), Move::Left][..],
+            &path[..4]
+        );
+        assert_eq!(13, path.len());
+        assert!(super::day22("..\n\n10X").is_err());
+    }
+
+    #[test]
+    fn day22_part1() {
+        assert_eq!(
+            Some(6032),
+            super::day22_part1(&super::day22(EXAMPLE).unwrap())
+        );
+    }
+
+    #[test]
+    fn day22_part2() {
+        assert_eq!(
+            5031,
+            super::day22_part2(&super::day22(EXAMPLE).unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn day22_cube_nets() {
+        // layout of the example and of the real inputs.
+        for layout in ["..#\n###\n..##", ".##\n.#\n##\n#"] {
+            let board = net(layout, 4);
+            let cube = Cube::fold(&board).unwrap();
+            assert_eq!(4, cube.size);
+            for (pos, tile) in board.iter() {
+                if *tile == Tile::Void {
+                    continue;
+                }
+                for dir in 0..4 {
+                    // walking 4 edges' length straight ahead goes around the cube.
+                    let mut state = (pos, dir);
+                    for _ in 0..16 {
+                        let (dx, dy) = super::DIRS[state.1];
+                        let next = (state.0 .0 + dx, state.0 .1 + dy);
+                        state = match board.get(next) {
+                            Some(Tile::Open) => (next, state.1),
+                            _ => super::Wrap::wrap(&cube, &board, state.0, state.1),
+                        };
+                    }
+                    assert_eq!((pos, dir), state, "{layout:?} from {pos:?} facing {dir}");
+                }
+            }
+        }
+        assert!(Cube::fold(&net("####\n####", 2)).is_err());
+        assert!(Cube::fold(&net("###", 2)).is_err());
+        assert!(Cube::fold(&net("######", 2)).is_err());
+        // the faces are all there but shifted down a row.
+        let err = Cube::fold(&net("...\n..#\n###\n..##", 2)).err().unwrap();
+        assert_eq!("first row is empty", err.to_string());
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{anyhow, Result};
+use yaah::{aoc, aoc_generator};
+
+use crate::{
+    point::{self, Point},
+    render::{Glyph, Raster},
+};
+
+/// Step of each direction looked at, in their initial order of priority: north, south, west and
+/// east.
+const DIRS: [Point; 4] = [(0, -1), (0, 1), (-1, 0), (1, 0)];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ground {
+    Empty,
+    Elf,
+}
+impl Glyph for Ground {
+    fn symbol(self) -> char {
+        match self {
+            Ground::Empty => '.',
+            Ground::Elf => '#',
+        }
+    }
+    fn color(self) -> [u8; 3] {
+        match self {
+            Ground::Empty => [16, 16, 24],
+            Ground::Elf => [90, 200, 90],
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Grove {
+    elves: HashSet<Point>,
+    rounds: usize,
+}
+impl Grove {
+    fn is_free(&self, (x, y): Point, cells: impl IntoIterator<Item = Point>) -> bool {
+        cells
+            .into_iter()
+            .all(|(dx, dy)| !self.elves.contains(&(x + dx, y + dy)))
+    }
+
+    /// Where the elf at `elf` proposes to move, if anywhere.
+    fn proposal(&self, elf: Point) -> Option<Point> {
+        let around = (-1..=1).flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)));
+        if self.is_free(elf, around.filter(|&d| d != (0, 0))) {
+            return None;
+        }
+        (0..DIRS.len())
+            .map(|i| DIRS[(self.rounds + i) % DIRS.len()])
+            .find(|&(dx, dy)| {
+                // the step and the cells on each side of it.
+                let side = [(dy, dx), (-dy, -dx)].map(|(sx, sy)| (dx + sx, dy + sy));
+                self.is_free(elf, [(dx, dy), side[0], side[1]])
+            })
+            .map(|(dx, dy)| (elf.0 + dx, elf.1 + dy))
+    }
+
+    /// Runs a round, returning whether any elf moved.
+    pub fn round(&mut self) -> bool {
+        let proposals: Vec<(Point, Point)> = self
+            .elves
+            .iter()
+            .filter_map(|&elf| Some((elf, self.proposal(elf)?)))
+            .collect();
+        let mut targets: HashMap<Point, usize> = HashMap::new();
+        proposals
+            .iter()
+            .for_each(|&(_, target)| *targets.entry(target).or_default() += 1);
+
+        let mut moved = false;
+        for (elf, target) in proposals {
+            if targets[&target] == 1 {
+                self.elves.remove(&elf);
+                self.elves.insert(target);
+                moved = true;
+            }
+        }
+        self.rounds += 1;
+        moved
+    }
+
+    /// Empty tiles in the smallest rectangle holding every elf.
+    pub fn empty_ground(&self) -> usize {
+        point::bounds(self.elves.iter().copied()).map_or(0, |((minx, miny), (maxx, maxy))| {
+            ((maxx - minx + 1) * (maxy - miny + 1)) as usize - self.elves.len()
+        })
+    }
+
+    pub fn render(&self) -> Option<Raster<Ground>> {
+        Raster::plot(
+            self.elves.iter().map(|&elf| (elf, Ground::Elf)),
+            Ground::Empty,
+        )
+    }
+}
+
+#[aoc_generator(day23)]
+fn day23(input: &str) -> Result<Grove> {
+    let elves = input
+        .lines()
+        .enumerate()
+        .flat_map(|(y, l)| {
+            l.chars().enumerate().filter_map(move |(x, c)| match c {
+                '#' => Some(Ok((x as i32, y as i32))),
+                '.' => None,
+                c => Some(Err(anyhow!("invalid tile `{c}`"))),
+            })
+        })
+        .collect::<Result<_>>()?;
+    Ok(Grove { elves, rounds: 0 })
+}
+
+#[aoc(day23, part1)]
+fn day23_part1(grove: &Grove) -> usize {
+    let mut grove = grove.clone();
+    (0..10).for_each(|_| {
+        grove.round();
+    });
+    grove.empty_ground()
+}
+
+#[aoc(day23, part2)]
+fn day23_part2(grove: &Grove) -> usize {
+    let mut grove = grove.clone();
+    while grove.round() {}
+    grove.rounds
+}
+
+#[cfg(test)]
+mod test {
+    const SMALL: &str = r".....
+..##.
+..#..
+.....
+..##.
+.....";
+
+    const EXAMPLE: &str = r"....#..
+..###.#
+#...#.#
+.#...##
+#.###..
+##.#.##
+.#..#..";
+
+    #[test]
+    fn day23_rounds() {
+        let mut grove = super::day23(SMALL).unwrap();
+        assert!(grove.round());
+        assert_eq!(
+            "\n##\n..\n#.\n.#\n#.\n",
+            grove.render().unwrap().to_string()
+        );
+        assert!(grove.round());
+        assert!(grove.round());
+        let expected = r"
+..#..
+....#
+#....
+....#
+.....
+..#..
+";
+        assert_eq!(expected, grove.render().unwrap().to_string());
+        assert!(!grove.round());
+        assert_eq!(4, grove.rounds);
+    }
+
+    #[test]
+    fn day23_part1() {
+        assert_eq!(110, super::day23_part1(&super::day23(EXAMPLE).unwrap()));
+    }
+
+    #[test]
+    fn day23_part2() {
+        assert_eq!(20, super::day23_part2(&super::day23(EXAMPLE).unwrap()));
+    }
+}
@@ -0,0 +1,172 @@
+use anyhow::{anyhow, bail, Result};
+use yaah::{aoc, aoc_generator};
+
+use crate::{pathfinding::bfs, point::Point};
+
+/// The valley within its walls, `(0, 0)` being its top left tile.
+///
+/// Blizzards only ever wrap around their row or column so each kind is kept as one bitset per
+/// row or column of their initial positions, shifted by the time when looking them up.
+#[derive(Debug)]
+pub struct Valley {
+    width: i32,
+    height: i32,
+    start: Point,
+    end: Point,
+    right: Vec<u128>,
+    left: Vec<u128>,
+    down: Vec<u128>,
+    up: Vec<u128>,
+}
+impl Valley {
+    /// Time after which the blizzards are back to their initial positions.
+    fn period(&self) -> usize {
+        fn gcd(a: usize, b: usize) -> usize {
+            if b == 0 {
+                a
+            } else {
+                gcd(b, a % b)
+            }
+        }
+        let (w, h) = (self.width as usize, self.height as usize);
+        w / gcd(w, h) * h
+    }
+
+    /// Whether `(x, y)` is free at `time`.
+    fn is_free(&self, (x, y): Point, time: usize) -> bool {
+        if (x, y) == self.start || (x, y) == self.end {
+            return true;
+        }
+        if !(0..self.width).contains(&x) || !(0..self.height).contains(&y) {
+            return false;
+        }
+        let (w, h) = (self.width as usize, self.height as usize);
+        let (x, y, t) = (x as usize, y as usize, time);
+        let blizzard = |bits: u128, i: usize| bits & (1 << i) != 0;
+        !(blizzard(self.right[y], (x + w - t % w) % w)
+            || blizzard(self.left[y], (x + t) % w)
+            || blizzard(self.down[x], (y + h - t % h) % h)
+            || blizzard(self.up[x], (y + t) % h))
+    }
+
+    /// Minutes needed to go from `from` to `to` leaving at `time`.
+    fn cross(&self, from: Point, to: Point, time: usize) -> Option<usize> {
+        let period = self.period();
+        bfs(
+            (from, time % period),
+            |((x, y), time)| {
+                let time = (time + 1) % period;
+                [(0, 0), (1, 0), (0, 1), (-1, 0), (0, -1)]
+                    .into_iter()
+                    .map(move |(dx, dy)| ((x + dx, y + dy), time))
+                    .filter(|&(p, time)| self.is_free(p, time))
+            },
+            |(p, _)| p == to,
+        )
+    }
+}
+
+#[aoc_generator(day24)]
+fn day24(input: &str) -> Result<Valley> {
+    let lines: Vec<&str> = input.lines().filter(|l| !l.is_empty()).collect();
+    let (first, last) = match &lines[..] {
+        [first, .., last] => (first, last),
+        _ => bail!("the valley needs at least two walls"),
+    };
+    let gap = |l: &str| {
+        l.find('.')
+            .map(|x| x as i32 - 1)
+            .ok_or_else(|| anyhow!("no gap in wall `{l}`"))
+    };
+    let inner = &lines[1..lines.len() - 1];
+    let width = first.len() as i32 - 2;
+    let height = inner.len() as i32;
+    if width <= 0 || width > 128 || height <= 0 || height > 128 {
+        bail!("unsupported valley size {width}x{height}");
+    }
+
+    let mut valley = Valley {
+        width,
+        height,
+        start: (gap(first)?, -1),
+        end: (gap(last)?, height),
+        right: vec![0; height as usize],
+        left: vec![0; height as usize],
+        down: vec![0; width as usize],
+        up: vec![0; width as usize],
+    };
+    for (y, l) in inner.iter().enumerate() {
+        let tiles = l
+            .strip_prefix('#')
+            .and_then(|l| l.strip_suffix('#'))
+            .filter(|tiles| tiles.len() == width as usize)
+            .ok_or_else(|| anyhow!("invalid row `{l}`"))?;
+        for (x, c) in tiles.chars().enumerate() {
+            match c {
+                '>' => valley.right[y] |= 1 << x,
+                '<' => valley.left[y] |= 1 << x,
+                'v' => valley.down[x] |= 1 << y,
+                '^' => valley.up[x] |= 1 << y,
+                '.' => {}
+                c => bail!("invalid tile `{c}`"),
+            }
+        }
+    }
+    Ok(valley)
+}
+
+#[aoc(day24, part1)]
+fn day24_part1(valley: &Valley) -> Option<usize> {
+    valley.cross(valley.start, valley.end, 0)
+}
+
+#[aoc(day24, part2)]
+fn day24_part2(valley: &Valley) -> Option<usize> {
+    let there = valley.cross(valley.start, valley.end, 0)?;
+    let back = there + valley.cross(valley.end, valley.start, there)?;
+    Some(back + valley.cross(valley.start, valley.end, back)?)
+}
+
+#[cfg(test)]
+mod test {
+    const EXAMPLE: &str = r"#.######
+#>>.<^<#
+#.<..<<#
+#>v.><>#
+#<^v^^>#
+######.#";
+
+    #[test]
+    fn day24_blizzards() {
+        let valley = super::day24(EXAMPLE).unwrap();
+        assert_eq!((6, 4, 12), (valley.width, valley.height, valley.period()));
+        assert_eq!(((0, -1), (5, 4)), (valley.start, valley.end));
+        // the state of the valley after 1 minute.
+        let expected = [".>3.<.", "<..<<.", ">2.22.", ">v..^<"];
+        for (y, row) in expected.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                let p = (x as i32, y as i32);
+                assert_eq!(c == '.', valley.is_free(p, 1), "{p:?}");
+                assert_eq!(c == '.', valley.is_free(p, 13), "{p:?}");
+            }
+        }
+        assert!(!valley.is_free((6, 0), 1));
+        assert!(!valley.is_free((1, -1), 1));
+    }
+
+    #[test]
+    fn day24_part1() {
+        assert_eq!(
+            Some(18),
+            super::day24_part1(&super::day24(EXAMPLE).unwrap())
+        );
+    }
+
+    #[test]
+    fn day24_part2() {
+        assert_eq!(
+            Some(54),
+            super::day24_part2(&super::day24(EXAMPLE).unwrap())
+        );
+    }
+}
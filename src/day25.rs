@@ -0,0 +1,232 @@
+use std::{
+    fmt,
+    iter::Sum,
+    ops::{Add, Neg},
+    str::FromStr,
+};
+
+use anyhow::{anyhow, Result};
+use yaah::{aoc, aoc_generator};
+
+/// Number written in balanced base 5, digits being `=`, `-`, `0`, `1` and `2` for -2 to 2.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Snafu(i128);
+
+impl Snafu {
+    /// `self + rhs`, `None` if it does not fit in an `i128`.
+    pub fn checked_add(self, rhs: Snafu) -> Option<Snafu> {
+        self.0.checked_add(rhs.0).map(Snafu)
+    }
+
+    /// `-self`, `None` for `i128::MIN`.
+    pub fn checked_neg(self) -> Option<Snafu> {
+        self.0.checked_neg().map(Snafu)
+    }
+}
+
+impl FromStr for Snafu {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s.is_empty() {
+            return Err(anyhow!("empty SNAFU number"));
+        }
+        s.chars()
+            .try_fold(0i128, |n, c| {
+                let digit = match c {
+                    '2' => 2,
+                    '1' => 1,
+                    '0' => 0,
+                    '-' => -1,
+                    '=' => -2,
+                    c => return Err(anyhow!("invalid SNAFU digit `{c}`")),
+                };
+                // 4n + digit + n rather than 5n + digit: 5n alone overflows for `i128::MIN`.
+                n.checked_mul(4)
+                    .and_then(|m| m.checked_add(digit))
+                    .and_then(|m| m.checked_add(n))
+                    .ok_or_else(|| anyhow!("`{s}` is too large"))
+            })
+            .map(Snafu)
+    }
+}
+
+impl fmt::Display for Snafu {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0 == 0 {
+            return f.write_str("0");
+        }
+        // digits are worked out from the least significant one, a remainder of 3 or 4 becoming -2 or
+        // -1 with a carry. Neither step can overflow, even at the bounds of `i128`.
+        let mut digits = Vec::new();
+        let mut n = self.0;
+        while n != 0 {
+            let (mut quotient, mut digit) = (n.div_euclid(5), n.rem_euclid(5));
+            if digit > 2 {
+                digit -= 5;
+                quotient += 1;
+            }
+            digits.push(b"=-012"[(digit + 2) as usize]);
+            n = quotient;
+        }
+        digits.reverse();
+        f.write_str(std::str::from_utf8(&digits).unwrap())
+    }
+}
+
+/// # Panics
+///
+/// If the sum does not fit in an `i128`, see [`Snafu::checked_add`].
+impl Add for Snafu {
+    type Output = Snafu;
+
+    fn add(self, rhs: Snafu) -> Snafu {
+        self.checked_add(rhs).expect("SNAFU addition overflowed")
+    }
+}
+
+/// # Panics
+///
+/// For `i128::MIN`, see [`Snafu::checked_neg`].
+impl Neg for Snafu {
+    type Output = Snafu;
+
+    fn neg(self) -> Snafu {
+        self.checked_neg().expect("SNAFU negation overflowed")
+    }
+}
+
+/// # Panics
+///
+/// If the sum does not fit in an `i128`.
+impl Sum for Snafu {
+    fn sum<I: Iterator<Item = Snafu>>(iter: I) -> Snafu {
+        iter.fold(Snafu(0), Add::add)
+    }
+}
+
+impl From<i64> for Snafu {
+    fn from(n: i64) -> Self {
+        Snafu(n.into())
+    }
+}
+impl From<i128> for Snafu {
+    fn from(n: i128) -> Self {
+        Snafu(n)
+    }
+}
+impl TryFrom<Snafu> for i64 {
+    type Error = std::num::TryFromIntError;
+
+    fn try_from(n: Snafu) -> Result<Self, Self::Error> {
+        n.0.try_into()
+    }
+}
+impl From<Snafu> for i128 {
+    fn from(n: Snafu) -> Self {
+        n.0
+    }
+}
+
+#[aoc_generator(day25)]
+fn day25(input: &str) -> Result<Vec<Snafu>> {
+    input
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(|l| l.trim().parse())
+        .collect()
+}
+
+#[aoc(day25, part1)]
+fn day25_part1(numbers: &[Snafu]) -> Snafu {
+    numbers.iter().copied().sum()
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use super::Snafu;
+
+    const EXAMPLE: &str = r"1=-0-2
+12111
+2=0=
+21
+2=01
+111
+20012
+112
+1=-1=
+1-12
+12
+1=
+122";
+
+    #[test]
+    fn day25_snafu() {
+        for (n, s) in [
+            (0i64, "0"),
+            (1, "1"),
+            (2, "2"),
+            (3, "1="),
+            (4, "1-"),
+            (5, "10"),
+            (8, "2="),
+            (10, "20"),
+            (15, "1=0"),
+            (20, "1-0"),
+            (2022, "1=11-2"),
+            (12345, "1-0---0"),
+            (314159265, "1121-1110-1=0"),
+            (-1, "-"),
+            (-3, "-2"),
+        ] {
+            assert_eq!(s, Snafu::from(n).to_string());
+            assert_eq!(Snafu::from(n), s.parse().unwrap());
+        }
+        assert!("".parse::<Snafu>().is_err());
+        assert!("12a".parse::<Snafu>().is_err());
+        assert!("2".repeat(60).parse::<Snafu>().is_err());
+        assert!(i64::try_from(Snafu::from(i128::MAX)).is_err());
+        for n in [i128::MIN, i128::MIN + 1, i128::MAX - 1, i128::MAX] {
+            assert_eq!(Snafu::from(n), Snafu::from(n).to_string().parse().unwrap());
+        }
+        assert_eq!(None, Snafu::from(i128::MAX).checked_add(Snafu::from(1i64)));
+        assert_eq!(None, Snafu::from(i128::MIN).checked_neg());
+    }
+
+    #[test]
+    #[should_panic(expected = "overflowed")]
+    fn day25_add_overflow() {
+        let _ = Snafu::from(i128::MAX) + Snafu::from(1i64);
+    }
+
+    #[test]
+    fn day25_part1() {
+        let numbers = super::day25(EXAMPLE).unwrap();
+        assert_eq!(4890, i64::try_from(super::day25_part1(&numbers)).unwrap());
+        assert_eq!("2=-1=0", super::day25_part1(&numbers).to_string());
+    }
+
+    proptest! {
+        #[test]
+        fn day25_round_trip(n in any::<i128>()) {
+            let snafu = Snafu::from(n);
+            prop_assert_eq!(n, snafu.into());
+            prop_assert_eq!(snafu, snafu.to_string().parse::<Snafu>().unwrap());
+            prop_assert_eq!(snafu.checked_neg(), n.checked_neg().map(Snafu::from));
+        }
+
+        #[test]
+        fn day25_add(a in any::<i128>(), b in any::<i128>()) {
+            let sum = Snafu::from(a).checked_add(Snafu::from(b));
+            prop_assert_eq!(a.checked_add(b).map(Snafu::from), sum);
+        }
+
+        #[test]
+        fn day25_sum(a in any::<i64>(), b in any::<i64>()) {
+            let sum: Snafu = [Snafu::from(a), Snafu::from(b)].into_iter().sum();
+            prop_assert_eq!(i128::from(a) + i128::from(b), sum.into());
+        }
+    }
+}
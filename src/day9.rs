@@ -4,46 +4,69 @@ use anyhow::{anyhow, Error};
 use itertools::Itertools;
 use yaah::aoc;
 
-fn draw(history: &HashSet<(i32, i32)>, rope: &[(i32, i32)]) {
-    let min_maxes = history.iter().fold((0, 0, 0, 0), |(a, b, c, d), &(x, y)| {
-        (a.min(x), b.max(x), c.min(y), d.max(y))
-    });
-    let (min_x, max_x, min_y, max_y) = rope.iter().fold(min_maxes, |(a, b, c, d), &(x, y)| {
-        (a.min(x), b.max(x), c.min(y), d.max(y))
-    });
+use crate::{
+    point::Point,
+    render::{Glyph, Raster},
+};
+
+/// How a cell of the rope's surroundings is drawn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Knot {
+    Empty,
+    Visited,
+    Start,
+    Head,
+    Tail,
+    /// Knot between the head and the tail, by its index in the rope.
+    Body(u8),
+}
+impl Glyph for Knot {
+    fn symbol(self) -> char {
+        match self {
+            Knot::Empty => '.',
+            Knot::Visited => '#',
+            Knot::Start => 's',
+            Knot::Head => 'H',
+            Knot::Tail => 'T',
+            Knot::Body(i) => char::from(b'0' + i),
+        }
+    }
+    fn color(self) -> [u8; 3] {
+        match self {
+            Knot::Empty => [16, 16, 24],
+            Knot::Visited => [60, 60, 90],
+            Knot::Start => [220, 220, 80],
+            Knot::Head => [230, 70, 60],
+            Knot::Tail => [80, 160, 230],
+            Knot::Body(_) => [200, 140, 60],
+        }
+    }
+}
 
-    let x_len = max_x - min_x + 1;
-    let y_len = max_y - min_y + 1;
-
-    let mut map = vec![b'.'; (x_len * y_len) as usize];
-    let mut place_at = |(x, y), c| {
-        let coord = x - min_x + (y - min_y) * x_len;
-        map[coord as usize] = c;
-    };
-    history.iter().for_each(|&pos| place_at(pos, b'#'));
-
-    place_at((0, 0), b's');
-    rope.iter().enumerate().rev().for_each(|(i, &pos)| {
-        let c = if i == (rope.len() - 1) {
-            b'T'
-        } else if i == 0 {
-            b'H'
+/// Positions visited by the tail with the rope on top, `y` growing upwards.
+pub fn draw(history: &HashSet<Point>, rope: &[Point]) -> Option<Raster<Knot>> {
+    let knots = rope.iter().enumerate().rev().map(|(i, &pos)| {
+        let knot = if i == 0 {
+            Knot::Head
+        } else if i == rope.len() - 1 {
+            Knot::Tail
         } else {
-            b'0' + (i as u8)
+            Knot::Body(i as u8)
         };
-        place_at(pos, c);
+        (pos, knot)
     });
-
-    //println!();
-    //println!("----------------------------");
-    //println!();
-
-    //map.chunks(x_len as usize).rev().for_each(|chunk| {
-    //    println!("{}", String::from_utf8_lossy(chunk));
-    //});
+    Raster::plot(
+        history
+            .iter()
+            .map(|&pos| (pos, Knot::Visited))
+            .chain([((0, 0), Knot::Start)])
+            .chain(knots)
+            .map(|((x, y), knot)| ((x, -y), knot)),
+        Knot::Empty,
+    )
 }
 
-fn update(head: (i32, i32), mut tail: (i32, i32)) -> (i32, i32) {
+fn update(head: Point, mut tail: Point) -> Point {
     assert!((head.0 - tail.0).abs() <= 2);
     assert!((head.1 - tail.1).abs() <= 2);
     if (head.0 - tail.0).abs() > 1 || (head.1 - tail.1).abs() > 1 {
@@ -74,10 +97,11 @@ fn update(head: (i32, i32), mut tail: (i32, i32)) -> (i32, i32) {
     tail
 }
 
-fn simulate(
+/// Moves the rope's head along `input`, recording each position of the tail in `history`.
+pub fn simulate(
     input: &str,
-    rope: &mut [(i32, i32)],
-    history: &mut HashSet<(i32, i32)>,
+    rope: &mut [Point],
+    history: &mut HashSet<Point>,
 ) -> anyhow::Result<()> {
     input.lines().try_for_each(|l| {
         let (dir, steps) = l
@@ -102,7 +126,6 @@ fn simulate(
         }
         Ok::<(), Error>(())
     })?;
-    draw(history, rope);
     Ok(())
 }
 
@@ -126,6 +149,27 @@ fn day9_part2(input: &'static str) -> anyhow::Result<usize> {
 
 #[cfg(test)]
 mod test {
+    use std::collections::HashSet;
+
+    #[test]
+    fn day9_draw() {
+        let mut rope = [(0, 0); 10];
+        let mut history = HashSet::new();
+        super::simulate("R 5\nU 8", &mut rope, &mut history).unwrap();
+        let expected = r"
+.....H
+.....1
+.....2
+.....3
+....54
+...6..
+..7...
+.8....
+T.....
+";
+        assert_eq!(expected, super::draw(&history, &rope).unwrap().to_string());
+    }
+
     #[test]
     fn day9_part1() {
         assert_eq!(
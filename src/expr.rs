@@ -0,0 +1,122 @@
+//! Arithmetic expressions of the monkey puzzles, e.g. `old * 19` or `pppw + sjmn`.
+
+use std::str::FromStr;
+
+use num_traits::Num;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+impl Op {
+    pub fn apply<T: Num>(self, lhs: T, rhs: T) -> T {
+        match self {
+            Op::Add => lhs + rhs,
+            Op::Sub => lhs - rhs,
+            Op::Mul => lhs * rhs,
+            Op::Div => lhs / rhs,
+        }
+    }
+
+    /// Like `apply` but `None` when dividing by zero.
+    pub fn checked_apply<T: Num>(self, lhs: T, rhs: T) -> Option<T> {
+        (self != Op::Div || !rhs.is_zero()).then(|| self.apply(lhs, rhs))
+    }
+
+    /// The `lhs` for which `lhs op rhs == result`, if there is a single one.
+    pub fn solve_lhs<T: Num>(self, rhs: T, result: T) -> Option<T> {
+        match self {
+            Op::Add => Some(result - rhs),
+            Op::Sub => Some(result + rhs),
+            Op::Mul => (!rhs.is_zero()).then(|| result / rhs),
+            Op::Div => (!rhs.is_zero()).then(|| result * rhs),
+        }
+    }
+
+    /// The `rhs` for which `lhs op rhs == result`, if there is a single one.
+    pub fn solve_rhs<T: Num>(self, lhs: T, result: T) -> Option<T> {
+        match self {
+            Op::Add => Some(result - lhs),
+            Op::Sub => Some(lhs - result),
+            Op::Mul => (!lhs.is_zero()).then(|| result / lhs),
+            Op::Div => (!result.is_zero()).then(|| lhs / result),
+        }
+    }
+}
+impl FromStr for Op {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "+" => Ok(Op::Add),
+            "-" => Ok(Op::Sub),
+            "*" => Ok(Op::Mul),
+            "/" => Ok(Op::Div),
+            s => Err(anyhow::anyhow!("unknown operator `{s}`")),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operand<V, N> {
+    Var(V),
+    Num(N),
+}
+
+/// Binary operation on variables of type `V` and numbers of type `N`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Expr<V, N> {
+    pub lhs: Operand<V, N>,
+    pub op: Op,
+    pub rhs: Operand<V, N>,
+}
+impl<V, N: Copy> Expr<V, N> {
+    pub fn new(lhs: Operand<V, N>, op: Op, rhs: Operand<V, N>) -> Self {
+        Self { lhs, op, rhs }
+    }
+
+    /// Parses `lhs op rhs`, operands that are not numbers being looked up with `var`.
+    pub fn parse(s: &str, mut var: impl FnMut(&str) -> Option<V>) -> anyhow::Result<Self>
+    where
+        N: FromStr,
+    {
+        let mut operand = |s: &str| match s.parse() {
+            Ok(n) => Ok(Operand::Num(n)),
+            Err(_) => var(s)
+                .map(Operand::Var)
+                .ok_or_else(|| anyhow::anyhow!("unknown variable `{s}`")),
+        };
+        match s.split_whitespace().collect::<Vec<_>>()[..] {
+            [lhs, op, rhs] => Ok(Self::new(operand(lhs)?, op.parse()?, operand(rhs)?)),
+            _ => Err(anyhow::anyhow!("expected `lhs op rhs`, got `{s}`")),
+        }
+    }
+
+    /// Evaluates the expression with the variables' values given by `var`.
+    pub fn eval<T: Num + From<N>>(&self, mut var: impl FnMut(&V) -> T) -> T {
+        let mut value = |operand: &Operand<V, N>| match operand {
+            Operand::Var(v) => var(v),
+            Operand::Num(n) => T::from(*n),
+        };
+        let lhs = value(&self.lhs);
+        self.op.apply(lhs, value(&self.rhs))
+    }
+
+    /// Like `eval` for fallible variable lookups, also failing on division by zero.
+    pub fn try_eval<T: Num + From<N>>(
+        &self,
+        mut var: impl FnMut(&V) -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        let mut value = |operand: &Operand<V, N>| match operand {
+            Operand::Var(v) => var(v),
+            Operand::Num(n) => Ok(T::from(*n)),
+        };
+        let lhs = value(&self.lhs)?;
+        self.op
+            .checked_apply(lhs, value(&self.rhs)?)
+            .ok_or_else(|| anyhow::anyhow!("division by zero"))
+    }
+}
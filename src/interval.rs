@@ -0,0 +1,225 @@
+use std::ops::RangeInclusive;
+
+use num_traits::PrimInt;
+
+/// Set of integers stored as sorted, disjoint and non-adjacent closed spans.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IntervalSet<T> {
+    spans: Vec<(T, T)>,
+}
+impl<T: PrimInt> IntervalSet<T> {
+    pub fn new() -> Self {
+        Self { spans: Vec::new() }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// Number of integers in the set.
+    ///
+    /// It is counted in a `u128` so that sets spanning most of `T` fit, only saturating for sets
+    /// of 128-bit integers holding all of them.
+    pub fn len(&self) -> u128 {
+        self.spans.iter().fold(0u128, |acc, &(start, end)| {
+            // `end - start` may not fit in `T`, it does in an `i128` unless `T` is `u128`.
+            let width = match (start.to_i128(), end.to_i128()) {
+                (Some(start), Some(end)) => end.abs_diff(start),
+                _ => end.to_u128().unwrap() - start.to_u128().unwrap(),
+            };
+            acc.saturating_add(width).saturating_add(1)
+        })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = RangeInclusive<T>> + '_ {
+        self.spans.iter().map(|&(start, end)| start..=end)
+    }
+
+    pub fn insert(&mut self, range: RangeInclusive<T>) {
+        let (mut start, mut end) = range.into_inner();
+        if start > end {
+            return;
+        }
+        // spans touching `start..=end`, adjacent ones included, are merged into it.
+        let lo = self
+            .spans
+            .partition_point(|&(_, e)| e < start.saturating_sub(T::one()));
+        let hi = self
+            .spans
+            .partition_point(|&(s, _)| s <= end.saturating_add(T::one()));
+        if lo < hi {
+            start = start.min(self.spans[lo].0);
+            end = end.max(self.spans[hi - 1].1);
+        }
+        self.spans.splice(lo..hi, [(start, end)]);
+    }
+
+    /// Adds every integer of `other` to the set.
+    pub fn merge(&mut self, other: &Self) {
+        other.iter().for_each(|range| self.insert(range));
+    }
+
+    pub fn intersect(&self, other: &Self) -> Self {
+        let mut spans = Vec::new();
+        let (mut a, mut b) = (self.spans.iter().peekable(), other.spans.iter().peekable());
+        while let (Some(&&(sa, ea)), Some(&&(sb, eb))) = (a.peek(), b.peek()) {
+            let (start, end) = (sa.max(sb), ea.min(eb));
+            if start <= end {
+                spans.push((start, end));
+            }
+            if ea < eb {
+                a.next();
+            } else {
+                b.next();
+            }
+        }
+        Self { spans }
+    }
+
+    /// Every integer of `T` not in the set.
+    pub fn complement(&self) -> Self {
+        let mut spans = Vec::with_capacity(self.spans.len() + 1);
+        let mut next = Some(T::min_value());
+        for &(start, end) in &self.spans {
+            if let Some(gap) = next.filter(|&gap| gap < start) {
+                spans.push((gap, start - T::one()));
+            }
+            next = end.checked_add(&T::one());
+        }
+        if let Some(gap) = next {
+            spans.push((gap, T::max_value()));
+        }
+        Self { spans }
+    }
+
+    pub fn subtract(&self, other: &Self) -> Self {
+        self.intersect(&other.complement())
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        let i = self.spans.partition_point(|&(_, end)| end < value);
+        self.spans.get(i).is_some_and(|&(start, _)| start <= value)
+    }
+
+    /// Whether every integer of `other` is in the set.
+    pub fn contains_range(&self, other: &Self) -> bool {
+        other.subtract(self).is_empty()
+    }
+
+    pub fn overlaps(&self, other: &Self) -> bool {
+        !self.intersect(other).is_empty()
+    }
+}
+impl<T: PrimInt> From<RangeInclusive<T>> for IntervalSet<T> {
+    fn from(range: RangeInclusive<T>) -> Self {
+        let mut set = Self::new();
+        set.insert(range);
+        set
+    }
+}
+impl<T: PrimInt> FromIterator<RangeInclusive<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = RangeInclusive<T>>>(iter: I) -> Self {
+        let mut ranges: Vec<_> = iter
+            .into_iter()
+            .map(RangeInclusive::into_inner)
+            .filter(|(start, end)| start <= end)
+            .collect();
+        ranges.sort_unstable();
+        let spans = ranges
+            .into_iter()
+            .fold(Vec::new(), |mut spans: Vec<(T, T)>, (start, end)| {
+                match spans.last_mut() {
+                    Some(last) if start <= last.1.saturating_add(T::one()) => {
+                        last.1 = last.1.max(end)
+                    }
+                    _ => spans.push((start, end)),
+                }
+                spans
+            });
+        Self { spans }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeSet;
+
+    use itertools::Itertools;
+    use proptest::prelude::*;
+
+    use super::{IntervalSet, RangeInclusive};
+
+    fn ranges() -> impl Strategy<Value = Vec<(i8, i8)>> {
+        prop::collection::vec((-20i8..20, -20i8..20), 0..8)
+    }
+
+    fn model(ranges: &[(i8, i8)]) -> BTreeSet<i8> {
+        ranges.iter().flat_map(|&(a, b)| a..=b).collect()
+    }
+
+    fn set(ranges: &[(i8, i8)]) -> IntervalSet<i8> {
+        ranges.iter().map(|&(a, b)| a..=b).collect()
+    }
+
+    fn points(set: &IntervalSet<i8>) -> BTreeSet<i8> {
+        set.iter().flatten().collect()
+    }
+
+    #[test]
+    fn interval_set_insert() {
+        let mut set = IntervalSet::new();
+        set.insert(5..=7);
+        set.insert(1..=2);
+        set.insert(10..=12);
+        assert_eq!(vec![1..=2, 5..=7, 10..=12], set.iter().collect_vec());
+        set.insert(3..=4);
+        assert_eq!(vec![1..=7, 10..=12], set.iter().collect_vec());
+        set.insert(6..=20);
+        assert_eq!(vec![1..=20], set.iter().collect_vec());
+        assert_eq!(20, set.len());
+        set.insert(RangeInclusive::new(30, 25));
+        assert_eq!(20, set.len());
+    }
+
+    #[test]
+    fn interval_set_bounds() {
+        let set = IntervalSet::from(i64::MIN..=i64::MAX);
+        assert_eq!(1 << 64, set.len());
+        assert!(set.complement().is_empty());
+        assert_eq!(set, IntervalSet::new().complement());
+        let set = IntervalSet::from(u8::MAX..=u8::MAX);
+        assert_eq!(vec![0..=254], set.complement().iter().collect_vec());
+        assert_eq!(255, set.complement().len());
+        assert_eq!(u128::MAX, IntervalSet::from(0..=u128::MAX).len());
+        assert_eq!(
+            1 << 127,
+            IntervalSet::from(u128::MAX / 2 + 1..=u128::MAX).len()
+        );
+        assert_eq!(u128::MAX, IntervalSet::from(i128::MIN..=i128::MAX).len());
+    }
+
+    proptest! {
+        #[test]
+        fn interval_set_ops(a in ranges(), b in ranges()) {
+            let (sa, sb) = (set(&a), set(&b));
+            let (ma, mb) = (model(&a), model(&b));
+
+            let mut inserted = IntervalSet::new();
+            a.iter().for_each(|&(start, end)| inserted.insert(start..=end));
+            prop_assert_eq!(&sa, &inserted);
+            prop_assert!(sa.iter().tuple_windows().all(|(x, y)| x.end() + 1 < *y.start()));
+
+            prop_assert_eq!(ma.len(), sa.len() as usize);
+            let mut merged = sa.clone();
+            merged.merge(&sb);
+            prop_assert_eq!(points(&merged), &ma | &mb);
+            prop_assert_eq!(points(&sa.intersect(&sb)), &ma & &mb);
+            prop_assert_eq!(points(&sa.subtract(&sb)), &ma - &mb);
+            prop_assert_eq!(sa.contains_range(&sb), mb.is_subset(&ma));
+            prop_assert_eq!(sa.overlaps(&sb), !ma.is_disjoint(&mb));
+            for v in -22..22 {
+                prop_assert_eq!(sa.contains(v), ma.contains(&v));
+            }
+        }
+    }
+}
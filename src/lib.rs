@@ -1,11 +1,10 @@
-use std::{
-    collections::{BTreeSet, HashSet},
-    ops::RangeInclusive,
-};
+use std::collections::{BTreeSet, HashSet};
 
 use itertools::Itertools;
 use yaah::{aoc, aoc_generator, aoc_lib, aoc_year};
 
+use interval::IntervalSet;
+
 aoc_year!(2022);
 
 #[aoc(day1, part1)]
@@ -113,7 +112,7 @@ fn day3_part2(input: &'static str) -> usize {
 
 type Day4List = std::iter::FilterMap<
     std::str::Lines<'static>,
-    fn(&str) -> Option<(RangeInclusive<usize>, RangeInclusive<usize>)>,
+    fn(&str) -> Option<(IntervalSet<usize>, IntervalSet<usize>)>,
 >;
 
 #[aoc_generator(day4)]
@@ -125,7 +124,7 @@ fn day4(input: &'static str) -> Day4List {
                     .split('-')
                     .filter_map(|v| v.parse().ok())
                     .collect_tuple()?;
-                Some(a..=b)
+                Some(IntervalSet::from(a..=b))
             })
             .collect_tuple()
     })
@@ -135,10 +134,7 @@ fn day4(input: &'static str) -> Day4List {
 fn day4_part1(input: &Day4List) -> usize {
     input
         .clone()
-        .filter(|(a, b)| {
-            a.contains(b.start()) && a.contains(b.end())
-                || b.contains(a.start()) && b.contains(a.end())
-        })
+        .filter(|(a, b)| a.contains_range(b) || b.contains_range(a))
         .count()
 }
 
@@ -146,12 +142,7 @@ fn day4_part1(input: &Day4List) -> usize {
 fn day4_part2(input: &Day4List) -> usize {
     input
         .clone()
-        .filter(|(a, b)| {
-            a.contains(b.start())
-                || a.contains(b.end())
-                || b.contains(a.start())
-                || b.contains(a.end())
-        })
+        .filter(|(a, b)| a.overlaps(b))
         .count()
 }
 
@@ -404,14 +395,31 @@ fn day8_part2((map, size): &(Vec<u8>, usize)) -> Option<usize> {
         .max()
 }
 
-mod day9;
+pub mod day9;
 
 mod day10;
-mod day11;
+pub mod day11;
 mod day12;
-mod day13;
-mod day14;
-mod day15;
+pub mod day13;
+pub mod day14;
+pub mod day15;
+mod day16;
+pub mod day17;
+mod day18;
+mod day19;
+mod day20;
+mod day21;
+mod day22;
+mod day23;
+mod day24;
+mod day25;
+
+pub mod expr;
+pub mod grid;
+pub mod interval;
+pub mod pathfinding;
+pub mod point;
+pub mod render;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,105 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    hash::Hash,
+};
+
+// reused from 2021 day 15
+pub fn a_star<N, T>(
+    origin: N,
+    target: N,
+    mut neighbours: impl FnMut(N) -> T,
+    mut distance: impl FnMut(N, N) -> usize,
+    mut heuristic: impl FnMut(N) -> usize,
+) -> Option<(usize, Vec<N>)>
+where
+    N: Copy + Ord + Hash,
+    T: Iterator<Item = N>,
+{
+    let mut open = priority_queue::PriorityQueue::new();
+    let mut came_from: BTreeMap<N, N> = BTreeMap::new();
+
+    let mut g_score = BTreeMap::new();
+    let mut f_score = BTreeMap::new();
+
+    open.push(origin, Reverse(0));
+    g_score.insert(origin, 0);
+    f_score.insert(origin, heuristic(origin));
+
+    fn score<N: Ord>(score_map: &BTreeMap<N, usize>, node: N) -> usize {
+        score_map.get(&node).copied().unwrap_or(usize::MAX)
+    }
+
+    while let Some((current, _)) = open.pop() {
+        if current == target {
+            // rebuild path
+            let mut current = current;
+            let mut path = vec![current];
+            while let Some(&origin) = came_from.get(&current) {
+                current = origin;
+                path.push(origin);
+            }
+            return Some((score(&g_score, target), path.into_iter().rev().collect()));
+        }
+
+        let current_g_score = score(&g_score, current);
+        for neighbour in neighbours(current) {
+            let tentative_g_score = current_g_score + distance(current, neighbour);
+            if tentative_g_score < score(&g_score, neighbour) {
+                let tentative_f_score = tentative_g_score + heuristic(neighbour);
+
+                came_from.insert(neighbour, current);
+                g_score.insert(neighbour, tentative_g_score);
+                f_score.insert(neighbour, tentative_f_score);
+                open.push(neighbour, Reverse(tentative_f_score));
+            }
+        }
+    }
+
+    None
+}
+
+/// Number of steps from `origin` to every node reachable from it.
+pub fn distances<N, T>(origin: N, mut neighbours: impl FnMut(N) -> T) -> HashMap<N, usize>
+where
+    N: Copy + Eq + Hash,
+    T: Iterator<Item = N>,
+{
+    let mut dist = HashMap::from([(origin, 0)]);
+    let mut queue = VecDeque::from([origin]);
+    while let Some(current) = queue.pop_front() {
+        let steps = dist[&current] + 1;
+        for neighbour in neighbours(current) {
+            dist.entry(neighbour).or_insert_with(|| {
+                queue.push_back(neighbour);
+                steps
+            });
+        }
+    }
+    dist
+}
+
+/// Number of steps from `origin` to the closest node for which `is_target` holds.
+pub fn bfs<N, T>(
+    origin: N,
+    mut neighbours: impl FnMut(N) -> T,
+    mut is_target: impl FnMut(N) -> bool,
+) -> Option<usize>
+where
+    N: Copy + Eq + Hash,
+    T: Iterator<Item = N>,
+{
+    let mut seen = HashSet::from([origin]);
+    let mut queue = VecDeque::from([(origin, 0)]);
+    while let Some((current, steps)) = queue.pop_front() {
+        if is_target(current) {
+            return Some(steps);
+        }
+        for neighbour in neighbours(current) {
+            if seen.insert(neighbour) {
+                queue.push_back((neighbour, steps + 1));
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,36 @@
+//! Coordinate types shared by the grid puzzles.
+
+pub type Point = (i32, i32);
+pub type Coord = (i64, i64);
+pub type Point3 = (i32, i32, i32);
+
+/// Smallest and largest coordinates of `points`, `None` if there are none.
+pub fn bounds(points: impl IntoIterator<Item = Point>) -> Option<(Point, Point)> {
+    points.into_iter().fold(None, |bounds, (x, y)| {
+        let ((minx, miny), (maxx, maxy)) = bounds.unwrap_or(((x, y), (x, y)));
+        Some(((minx.min(x), miny.min(y)), (maxx.max(x), maxy.max(y))))
+    })
+}
+
+/// Smallest and largest coordinates of `points` along each axis, `None` if there are none.
+pub fn bounds3(points: impl IntoIterator<Item = Point3>) -> Option<(Point3, Point3)> {
+    points.into_iter().fold(None, |bounds, (x, y, z)| {
+        let ((minx, miny, minz), (maxx, maxy, maxz)) = bounds.unwrap_or(((x, y, z), (x, y, z)));
+        Some((
+            (minx.min(x), miny.min(y), minz.min(z)),
+            (maxx.max(x), maxy.max(y), maxz.max(z)),
+        ))
+    })
+}
+
+/// The six points sharing a face with `(x, y, z)`.
+pub fn neighbours3((x, y, z): Point3) -> [Point3; 6] {
+    [
+        (x - 1, y, z),
+        (x + 1, y, z),
+        (x, y - 1, z),
+        (x, y + 1, z),
+        (x, y, z - 1),
+        (x, y, z + 1),
+    ]
+}
@@ -0,0 +1,107 @@
+use std::io::Write;
+
+use itertools::Itertools;
+
+use crate::point::{self, Point};
+
+/// How a cell is drawn, as a character in a terminal or as a color in an image.
+pub trait Glyph: Copy {
+    fn symbol(self) -> char;
+    fn color(self) -> [u8; 3];
+}
+
+/// Rectangular grid of cells.
+///
+/// It prints as ASCII and can be written as a PPM (or PNG with the `png` feature) image for inputs
+/// too large to fit in a terminal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Raster<T> {
+    width: usize,
+    cells: Vec<T>,
+}
+impl<T: Glyph> Raster<T> {
+    /// Builds a raster from its cells in row-major order.
+    pub fn new(width: usize, cells: Vec<T>) -> Self {
+        assert!(
+            width > 0 && cells.len().is_multiple_of(width),
+            "cells do not fill complete rows"
+        );
+        Self { width, cells }
+    }
+
+    /// Draws the bounding box of sparse `cells` over a `background`, `None` if there are none.
+    pub fn plot(cells: impl IntoIterator<Item = (Point, T)>, background: T) -> Option<Self> {
+        let cells: Vec<(Point, T)> = cells.into_iter().collect();
+        let ((minx, miny), (maxx, maxy)) = point::bounds(cells.iter().map(|&(p, _)| p))?;
+        let width = (maxx - minx + 1) as usize;
+        let mut raster = Self::new(width, vec![background; width * (maxy - miny + 1) as usize]);
+        for ((x, y), cell) in cells {
+            raster.cells[(x - minx) as usize + (y - miny) as usize * width] = cell;
+        }
+        Some(raster)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.cells.len() / self.width
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<T> {
+        (x < self.width)
+            .then(|| self.cells.get(x + y * self.width).copied())
+            .flatten()
+    }
+
+    /// RGB pixels with each cell drawn as a `scale`×`scale` square.
+    fn rgb(&self, scale: usize) -> Vec<u8> {
+        self.cells
+            .chunks(self.width)
+            .flat_map(|row| {
+                let line = row
+                    .iter()
+                    .flat_map(|cell| std::iter::repeat_n(cell.color(), scale))
+                    .flatten()
+                    .collect_vec();
+                std::iter::repeat_n(line, scale).flatten()
+            })
+            .collect()
+    }
+
+    pub fn write_ppm(&self, mut w: impl Write, scale: usize) -> std::io::Result<()> {
+        writeln!(
+            w,
+            "P6\n{} {}\n255",
+            self.width() * scale,
+            self.height() * scale
+        )?;
+        w.write_all(&self.rgb(scale))
+    }
+
+    #[cfg(feature = "png")]
+    pub fn write_png(&self, w: impl Write, scale: usize) -> anyhow::Result<()> {
+        let mut encoder = png::Encoder::new(
+            w,
+            (self.width() * scale) as u32,
+            (self.height() * scale) as u32,
+        );
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&self.rgb(scale))?;
+        Ok(())
+    }
+}
+impl<T: Glyph> std::fmt::Display for Raster<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f)?;
+        self.cells.chunks(self.width).try_for_each(|line| {
+            writeln!(
+                f,
+                "{}",
+                line.iter().map(|cell| cell.symbol()).collect::<String>()
+            )
+        })
+    }
+}